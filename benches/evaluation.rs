@@ -0,0 +1,242 @@
+//! Benchmarks comparing the substitution-based strategies with the abstract machines and
+//! normalization by evaluation.
+//!
+//! Run with `cargo bench`. The `times` workload multiplies two Church numerals and uses the result
+//! to apply the identity function, so that every strategy has to do the same amount of work to
+//! reach a value. The `plus` workload adds two Church numerals, and needs a strategy that reduces
+//! under abstractions to reach the normal form. The `church_numerals` workload scales up
+//! `examples/church_numerals.lambda`, and compares evaluating in place with stepping a copy of the
+//! whole term each time.
+//!
+//! The suite at the end times the small-step strategies, on both notations, over the workloads in
+//! `WORKLOADS`. The small ones are the workloads profiled by `test_profiles_workloads`; the large
+//! ones scale each up far enough that the time per step, rather than the setup, dominates.
+#![feature(test)]
+
+extern crate test;
+
+use test::Bencher;
+
+use lambda_calculus::evaluation::{Cek, CallByValue, EmptyContext, Evaluable, Full, Krivine, Lazy, LocallyNamelessNormal, Nbe, Normal, SharedNormal};
+use lambda_calculus::notation::{locally_nameless, DeBruijn};
+use lambda_calculus::notation::named::Term;
+use lambda_calculus::notation::shared::Store;
+use lambda_calculus::parser::{parse, parse_one};
+
+fn church_numeral(n: usize) -> String {
+    format!(r"(\s.\z.{}z{})", "s (".repeat(n), ")".repeat(n))
+}
+
+fn times_workload(n: usize) -> Term {
+    let numeral = church_numeral(n);
+    parse_one(&format!(r"(\m.\n.\s.m (n s)) {} {} (\f.f) (\x.x)", numeral, numeral)).unwrap()
+}
+
+fn plus_workload(n: usize) -> Term {
+    let numeral = church_numeral(n);
+    parse_one(&format!(r"(\m.\n.\s.\z.m s (n s z)) {} {}", numeral, numeral)).unwrap()
+}
+
+/// Defines the numerals up to `n` on top of `examples/church_numerals.lambda`, each the successor
+/// of the last, and adds the largest to itself
+fn church_numerals_workload(n: usize) -> Term {
+    let mut text = format!("{}\n", include_str!("../examples/church_numerals.lambda"));
+    for k in 4..=n {
+        text += &format!("c_{} = scc c_{};\n", k, k - 1);
+    }
+    text += &format!("plus c_{} c_{};\n", n, n);
+    parse(&text).unwrap().pop().unwrap()
+}
+
+#[bench]
+fn bench_normal(b: &mut Bencher) {
+    let term = times_workload(10);
+    b.iter(|| Normal::new().evaluate(&mut EmptyContext{}, term.clone()));
+}
+
+#[bench]
+fn bench_call_by_value(b: &mut Bencher) {
+    let term = times_workload(10);
+    b.iter(|| CallByValue::new().evaluate(&mut EmptyContext{}, term.clone()));
+}
+
+#[bench]
+fn bench_krivine(b: &mut Bencher) {
+    let term = times_workload(10);
+    b.iter(|| Krivine::new().evaluate(&term));
+}
+
+#[bench]
+fn bench_cek(b: &mut Bencher) {
+    let term = times_workload(10);
+    b.iter(|| Cek::new().evaluate(&term));
+}
+
+#[bench]
+fn bench_normal_plus(b: &mut Bencher) {
+    let term = plus_workload(100);
+    b.iter(|| Normal::new().evaluate(&mut EmptyContext{}, term.clone()));
+}
+
+#[bench]
+fn bench_nbe_plus(b: &mut Bencher) {
+    let term = plus_workload(100);
+    b.iter(|| Nbe::new().normalize(&term));
+}
+
+#[bench]
+fn bench_shared_normal_plus(b: &mut Bencher) {
+    let term = DeBruijn::from(&plus_workload(100));
+    b.iter(|| {
+        let mut store = Store::new();
+        let term = store.import(&term);
+        SharedNormal::new().evaluate(&mut store, term)
+    });
+}
+
+#[bench]
+fn bench_normal_church_numerals(b: &mut Bencher) {
+    let term = church_numerals_workload(100);
+    b.iter(|| Normal::new().evaluate(&mut EmptyContext{}, term.clone()));
+}
+
+#[bench]
+fn bench_normal_church_numerals_stepping_copies(b: &mut Bencher) {
+    // How `evaluate` used to work, searching from the root for each redex in a copy of the term
+    let term = church_numerals_workload(100);
+    b.iter(|| {
+        let mut term = term.clone();
+        while let Some(new_term) = Normal::new().step(&mut EmptyContext{}, term.clone()) {
+            term = new_term;
+        }
+        term
+    });
+}
+
+/// Numerals too big to build from the prelude's cheaply, written out in full
+const LARGE_NUMERALS: &[usize] = &[10, 20, 100];
+
+/// Each workload, written on top of the definitions in `examples/workloads.lambda`, computes a
+/// numeral and applies it to the identity twice, so that even the weak strategies have to do all
+/// of the work to reach `\y.y`.
+const WORKLOADS: &[(&str, &str)] = &[
+    ("plus", "plus c5 c5"),
+    ("times", "times c4 c5"),
+    ("exp", "exp c2 c4"),
+    ("factorial", "fact c3"),
+    ("ackermann", "ack c2 c2"),
+    ("sort", "weigh (sort (cons c3 (cons c1 (cons c2 nil))))"),
+    ("plus_large", "plus c100 c100"),
+    ("times_large", "times c10 c20"),
+    ("exp_large", "exp c2 c10"),
+    ("factorial_large", "fact c4"),
+    ("ackermann_large", "ack c3 c2"),
+    ("sort_large", "weigh (sort (cons c4 (cons c2 (cons c5 (cons c3 (cons c1 nil))))))"),
+];
+
+fn workload(name: &str) -> Term {
+    let (_, expression) = WORKLOADS.iter().find(|(n, _)| *n == name).expect("a known workload");
+    let mut text = include_str!("../examples/workloads.lambda").to_string();
+    for n in LARGE_NUMERALS {
+        text += &format!("c{} = {};\n", n, church_numeral(*n));
+    }
+    text += &format!(r"{} (\x.x) (\y.y);", expression);
+    parse(&text).unwrap().pop().unwrap()
+}
+
+/// Times evaluating a workload with a strategy, for each combination that finishes. Call by value
+/// and full reduction evaluate the argument of `Y` before unfolding it, so never finish a workload
+/// that recurses through it.
+macro_rules! suite {
+    ($($bench:ident: $strategy:ident $workload:expr;)*) => {
+        $(
+            #[bench]
+            fn $bench(b: &mut Bencher) {
+                let term = workload($workload);
+                suite!(@run $strategy, b, term);
+            }
+        )*
+    };
+    (@run locally_nameless, $b:ident, $term:ident) => {
+        let term = locally_nameless::Term::from(&$term);
+        $b.iter(|| LocallyNamelessNormal::new().evaluate(&mut EmptyContext{}, term.clone()));
+    };
+    (@run shared, $b:ident, $term:ident) => {
+        let term = DeBruijn::from(&$term);
+        $b.iter(|| {
+            let mut store = Store::new();
+            let term = store.import(&term);
+            SharedNormal::new().evaluate(&mut store, term)
+        });
+    };
+    (@run $strategy:ident, $b:ident, $term:ident) => {
+        $b.iter(|| $strategy::new().evaluate(&mut EmptyContext{}, $term.clone()));
+    };
+}
+
+suite! {
+    bench_suite_plus_normal: Normal "plus";
+    bench_suite_plus_call_by_value: CallByValue "plus";
+    bench_suite_plus_lazy: Lazy "plus";
+    bench_suite_plus_full: Full "plus";
+    bench_suite_plus_locally_nameless: locally_nameless "plus";
+    bench_suite_plus_shared: shared "plus";
+    bench_suite_times_normal: Normal "times";
+    bench_suite_times_call_by_value: CallByValue "times";
+    bench_suite_times_lazy: Lazy "times";
+    bench_suite_times_full: Full "times";
+    bench_suite_times_locally_nameless: locally_nameless "times";
+    bench_suite_times_shared: shared "times";
+    bench_suite_exp_normal: Normal "exp";
+    bench_suite_exp_call_by_value: CallByValue "exp";
+    bench_suite_exp_lazy: Lazy "exp";
+    bench_suite_exp_full: Full "exp";
+    bench_suite_exp_locally_nameless: locally_nameless "exp";
+    bench_suite_exp_shared: shared "exp";
+    bench_suite_factorial_normal: Normal "factorial";
+    bench_suite_factorial_lazy: Lazy "factorial";
+    bench_suite_factorial_locally_nameless: locally_nameless "factorial";
+    bench_suite_factorial_shared: shared "factorial";
+    bench_suite_ackermann_normal: Normal "ackermann";
+    bench_suite_ackermann_call_by_value: CallByValue "ackermann";
+    bench_suite_ackermann_lazy: Lazy "ackermann";
+    bench_suite_ackermann_full: Full "ackermann";
+    bench_suite_ackermann_locally_nameless: locally_nameless "ackermann";
+    bench_suite_ackermann_shared: shared "ackermann";
+    bench_suite_sort_normal: Normal "sort";
+    bench_suite_sort_lazy: Lazy "sort";
+    bench_suite_sort_locally_nameless: locally_nameless "sort";
+    bench_suite_sort_shared: shared "sort";
+    bench_suite_plus_large_normal: Normal "plus_large";
+    bench_suite_plus_large_call_by_value: CallByValue "plus_large";
+    bench_suite_plus_large_lazy: Lazy "plus_large";
+    bench_suite_plus_large_full: Full "plus_large";
+    bench_suite_plus_large_locally_nameless: locally_nameless "plus_large";
+    bench_suite_plus_large_shared: shared "plus_large";
+    bench_suite_times_large_normal: Normal "times_large";
+    bench_suite_times_large_call_by_value: CallByValue "times_large";
+    bench_suite_times_large_lazy: Lazy "times_large";
+    bench_suite_times_large_full: Full "times_large";
+    bench_suite_times_large_locally_nameless: locally_nameless "times_large";
+    bench_suite_times_large_shared: shared "times_large";
+    bench_suite_exp_large_normal: Normal "exp_large";
+    bench_suite_exp_large_call_by_value: CallByValue "exp_large";
+    bench_suite_exp_large_lazy: Lazy "exp_large";
+    bench_suite_exp_large_full: Full "exp_large";
+    bench_suite_exp_large_locally_nameless: locally_nameless "exp_large";
+    bench_suite_exp_large_shared: shared "exp_large";
+    bench_suite_factorial_large_normal: Normal "factorial_large";
+    bench_suite_factorial_large_lazy: Lazy "factorial_large";
+    bench_suite_factorial_large_locally_nameless: locally_nameless "factorial_large";
+    bench_suite_factorial_large_shared: shared "factorial_large";
+    bench_suite_ackermann_large_normal: Normal "ackermann_large";
+    bench_suite_ackermann_large_call_by_value: CallByValue "ackermann_large";
+    bench_suite_ackermann_large_lazy: Lazy "ackermann_large";
+    bench_suite_ackermann_large_full: Full "ackermann_large";
+    bench_suite_ackermann_large_locally_nameless: locally_nameless "ackermann_large";
+    bench_suite_ackermann_large_shared: shared "ackermann_large";
+    bench_suite_sort_large_normal: Normal "sort_large";
+    bench_suite_sort_large_lazy: Lazy "sort_large";
+    bench_suite_sort_large_locally_nameless: locally_nameless "sort_large";
+    bench_suite_sort_large_shared: shared "sort_large";
+}
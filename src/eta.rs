@@ -0,0 +1,273 @@
+//! Eta conversion
+//!
+//! An abstraction of the form `λx.t x`, where `x` isn't free in `t`, behaves the same as `t` when
+//! applied to any argument. Replacing the former with the latter is eta reduction, and the
+//! reverse is eta expansion.
+use crate::evaluation::Nbe;
+use crate::notation::{DeBruijn, Named};
+use crate::path::{subterm_at_mut, Dir, Path};
+use crate::vars::Variables;
+
+const PRIME: &str = "'";
+
+/// A term that supports eta conversion
+pub trait Eta : Sized {
+    /// Returns this term with every eta redex contracted, including those inside other terms
+    fn eta_reduce(&self) -> Self;
+
+    /// Returns this term wrapped in an abstraction that applies it to the bound variable
+    fn eta_expand(&self) -> Self;
+
+    /// Contracts the leftmost outermost eta redex in this term
+    fn eta_step(&self) -> Option<Self>;
+
+    /// Contracts the leftmost outermost eta redex in this term in place, returning false and
+    /// leaving the term as it is if there isn't one
+    fn eta_step_in_place(&mut self) -> bool {
+        match self.eta_step() {
+            Some(term) => {
+                *self = term;
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl Eta for Named {
+    fn eta_reduce(&self) -> Self {
+        match self {
+            Named::Variable(_) => {
+                self.clone()
+            },
+            Named::Abstraction(name, body) => {
+                let mut body = body.eta_reduce();
+                match &mut body {
+                    Named::Application(t, box Named::Variable(arg)) if arg == name && !t.occurs_free(name) => t.take(),
+                    _ => Named::Abstraction(name.clone(), box body),
+                }
+            },
+            Named::Application(t1, t2) => {
+                Named::Application(box t1.eta_reduce(), box t2.eta_reduce())
+            },
+        }
+    }
+
+    fn eta_expand(&self) -> Self {
+        let free_variables = self.free_variables();
+        let mut name = "x".to_string();
+        while free_variables.contains(&name) {
+            name += PRIME;
+        }
+
+        Named::Abstraction(name.clone(), box Named::Application(box self.clone(), box Named::Variable(name)))
+    }
+
+    fn eta_step(&self) -> Option<Self> {
+        let path = leftmost_outermost_eta_redex(self)?;
+        let mut term = self.clone();
+        contract_eta_redex(&mut term, &path);
+        Some(term)
+    }
+
+    fn eta_step_in_place(&mut self) -> bool {
+        match leftmost_outermost_eta_redex(self) {
+            Some(path) => {
+                contract_eta_redex(self, &path);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// Returns the path to the leftmost outermost eta redex in the term, searching with an explicit
+/// stack so that deep terms don't overflow
+fn leftmost_outermost_eta_redex(term: &Named) -> Option<Path> {
+    let mut path = Vec::new();
+    let mut work = vec![(term, 0, None)];
+    while let Some((term, parent_length, dir)) = work.pop() {
+        path.truncate(parent_length);
+        path.extend(dir);
+        match term {
+            Named::Variable(_) => {},
+            Named::Abstraction(name, box Named::Application(t, box Named::Variable(arg))) if arg == name && !t.occurs_free(name) => {
+                return Some(path);
+            },
+            Named::Abstraction(_, body) => {
+                work.push((body, path.len(), Some(Dir::Body)));
+            },
+            Named::Application(t1, t2) => {
+                work.push((t2, path.len(), Some(Dir::Arg)));
+                work.push((t1, path.len(), Some(Dir::Fun)));
+            },
+        }
+    }
+    None
+}
+
+/// Replaces the eta redex `λx.t x` at the given path with `t`, without copying `t`
+fn contract_eta_redex(term: &mut Named, path: &[Dir]) {
+    let redex = subterm_at_mut(term, path).expect("the path leads to a subterm");
+    let contracted = match redex {
+        Named::Abstraction(_, box Named::Application(t, _)) => t.take(),
+        _ => unreachable!("the path leads to an eta redex"),
+    };
+    *redex = contracted;
+}
+
+impl Eta for DeBruijn {
+    fn eta_reduce(&self) -> Self {
+        match self {
+            DeBruijn::Variable(_) => {
+                self.clone()
+            },
+            DeBruijn::Abstraction(body) => {
+                match body.eta_reduce() {
+                    DeBruijn::Application(ref t, box DeBruijn::Variable(0)) if !t.has_free_variable(0) => t.unshift(),
+                    body => DeBruijn::Abstraction(box body),
+                }
+            },
+            DeBruijn::Application(t1, t2) => {
+                DeBruijn::Application(box t1.eta_reduce(), box t2.eta_reduce())
+            },
+        }
+    }
+
+    fn eta_expand(&self) -> Self {
+        DeBruijn::Abstraction(box DeBruijn::Application(box self.shift(1), box DeBruijn::Variable(0)))
+    }
+
+    fn eta_step(&self) -> Option<Self> {
+        match self {
+            DeBruijn::Variable(_) => {
+                None
+            },
+            DeBruijn::Abstraction(box DeBruijn::Application(box t, box DeBruijn::Variable(0))) if !t.has_free_variable(0) => {
+                Some(t.unshift())
+            },
+            DeBruijn::Abstraction(body) => {
+                body.eta_step().map(|body| DeBruijn::Abstraction(box body))
+            },
+            DeBruijn::Application(t1, t2) => {
+                match t1.eta_step() {
+                    Some(t1) => Some(DeBruijn::Application(box t1, t2.clone())),
+                    None => t2.eta_step().map(|t2| DeBruijn::Application(t1.clone(), box t2)),
+                }
+            },
+        }
+    }
+}
+
+/// Returns true if the two terms are equal up to beta and eta conversion.
+///
+/// This compares the beta-eta normal forms of the terms, so it will not terminate if either of
+/// them has no normal form.
+pub fn is_beta_eta_equivalent(t1: &Named, t2: &Named) -> bool {
+    let nbe = Nbe::new();
+    nbe.normalize(t1).eta_reduce().is_alpha_equivalent(&nbe.normalize(t2).eta_reduce())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_one;
+    use crate::notation::de_bruijn::{l, a};
+
+    fn assert_eta_reduces_to(expected: &str, expr: &str) {
+        assert_eq!(
+            parse_one(expected).unwrap(),
+            parse_one(expr).unwrap().eta_reduce()
+        );
+    }
+
+    #[test]
+    fn test_reduces_simple_eta_redex() {
+        assert_eta_reduces_to("f", r"\x.f x");
+    }
+
+    #[test]
+    fn test_does_not_reduce_when_bound_variable_is_free_in_function() {
+        assert_eta_reduces_to(r"\x.x x", r"\x.x x");
+    }
+
+    #[test]
+    fn test_reduces_nested_eta_redexes() {
+        assert_eta_reduces_to("f", r"\x.\y.f x y");
+    }
+
+    #[test]
+    fn test_reduces_church_numeral_one() {
+        assert_eta_reduces_to(r"\s.s", r"\s.\z.s z");
+    }
+
+    #[test]
+    fn test_reduces_inside_application() {
+        assert_eta_reduces_to(r"g f", r"g (\x.f x)");
+    }
+
+    #[test]
+    fn test_eta_expands_with_fresh_name() {
+        assert_eq!(
+            parse_one(r"\x''.x x' x''").unwrap(),
+            parse_one(r"x x'").unwrap().eta_expand()
+        );
+    }
+
+    #[test]
+    fn test_eta_step_contracts_outermost_redex() {
+        assert_eq!(
+            Some(parse_one(r"\x.f x").unwrap()),
+            parse_one(r"\y.(\x.f x) y").unwrap().eta_step()
+        );
+    }
+
+    #[test]
+    fn test_eta_step_in_place_contracts_redex_inside_application() {
+        let mut term = parse_one(r"g (\x.(\x.x) x) (\y.f y)").unwrap();
+        assert!(term.eta_step_in_place());
+        assert_eq!(parse_one(r"g (\x.x) (\y.f y)").unwrap(), term);
+        assert!(term.eta_step_in_place());
+        assert_eq!(parse_one(r"g (\x.x) f").unwrap(), term);
+        assert!(!term.eta_step_in_place());
+    }
+
+    #[test]
+    fn test_de_bruijn_eta_reduces_with_unshift() {
+        assert_eq!(
+            l(a(0u32, 1u32)),
+            l(l(a(a(1u32, 2u32), 0u32))).eta_reduce()
+        );
+    }
+
+    #[test]
+    fn test_de_bruijn_does_not_reduce_when_bound_variable_is_free_in_function() {
+        assert_eq!(
+            l(a(0u32, 0u32)),
+            l(a(0u32, 0u32)).eta_reduce()
+        );
+    }
+
+    #[test]
+    fn test_de_bruijn_eta_expands_with_shift() {
+        assert_eq!(
+            l(a(a(1u32, 2u32), 0u32)),
+            a(0u32, 1u32).eta_expand()
+        );
+    }
+
+    #[test]
+    fn test_beta_eta_equivalent_church_numeral_one_and_identity() {
+        assert!(is_beta_eta_equivalent(&parse_one(r"\s.\z.s z").unwrap(), &parse_one(r"\s.s").unwrap()));
+    }
+
+    #[test]
+    fn test_beta_eta_equivalent_after_beta_reduction() {
+        assert!(is_beta_eta_equivalent(&parse_one(r"(\f.\x.f x) g").unwrap(), &parse_one(r"g").unwrap()));
+    }
+
+    #[test]
+    fn test_not_beta_eta_equivalent() {
+        assert!(!is_beta_eta_equivalent(&parse_one(r"\s.\z.z").unwrap(), &parse_one(r"\s.s").unwrap()));
+    }
+}
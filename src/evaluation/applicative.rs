@@ -0,0 +1,169 @@
+//! Applicative order reduction strategy
+//!
+//! This strategy reduces the leftmost innermost redex first, including inside abstractions, so
+//! the function and argument of a redex are both in normal form before it is contracted.
+use crate::notation::named::Term;
+use crate::path::{contract_in_place, Dir};
+
+use super::Evaluable;
+
+#[derive(Default)]
+pub struct Applicative;
+
+impl Applicative {
+    pub fn new() -> Applicative {
+        Applicative {}
+    }
+}
+
+impl Evaluable for Applicative {
+    type Term = Term;
+    type Context = super::EmptyContext;
+
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        match next_redex(term) {
+            Some(path) => contract_in_place(term, &path),
+            None => false,
+        }
+    }
+}
+
+/// Returns the path to the leftmost innermost redex: the first redex to be left by a traversal
+/// that visits the function and then the argument of an application before the application.
+///
+/// A term is only searched once, rather than checking whether each subterm on the way down is in
+/// normal form, and the search keeps its own stack so that deep terms don't overflow.
+fn next_redex(term: &Term) -> Option<Vec<Dir>> {
+    enum Work<'t> {
+        Enter(&'t Term, usize, Option<Dir>),
+        Exit(usize),
+    }
+
+    let mut path = Vec::new();
+    let mut work = vec![Work::Enter(term, 0, None)];
+    while let Some(item) = work.pop() {
+        match item {
+            Work::Enter(term, parent_length, dir) => {
+                path.truncate(parent_length);
+                path.extend(dir);
+                match term {
+                    Term::Variable(_) => {},
+                    Term::Abstraction(_, body) => {
+                        work.push(Work::Enter(body, path.len(), Some(Dir::Body)));
+                    },
+                    Term::Application(t1, t2) => {
+                        // Only redexes need to be exited
+                        if t1.is_value() {
+                            work.push(Work::Exit(path.len()));
+                        }
+                        work.push(Work::Enter(t2, path.len(), Some(Dir::Arg)));
+                        work.push(Work::Enter(t1, path.len(), Some(Dir::Fun)));
+                    },
+                }
+            },
+            Work::Exit(length) => {
+                path.truncate(length);
+                return Some(path);
+            },
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_one;
+    use crate::evaluation::{EmptyContext, Normal};
+    use crate::evaluation::testing::{assert_agrees_when_both_terminate, assert_steps_as_defined};
+    use crate::substitution::Substitutable;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        assert_eq!(
+            parse_one(expected).unwrap(),
+            Applicative::new().evaluate(&mut EmptyContext{}, parse_one(expr).unwrap())
+        )
+    }
+
+    fn assert_steps_to(expected: &str, expr: &str) {
+        assert_eq!(
+            Some(parse_one(expected).unwrap()),
+            Applicative::new().step(&mut EmptyContext{}, parse_one(expr).unwrap())
+        )
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_abstraction() {
+        assert_evaluates_to(r"\x.z x", r"\x.((\y.y) z) x");
+    }
+
+    #[test]
+    pub fn test_evaluates_application_with_variable_on_left() {
+        assert_evaluates_to(r"z (\x.x) (\z.z)", r"z ((\y.y) \x.x) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_argument_before_contracting() {
+        assert_steps_to(r"(\x.x x) z", r"(\x.x x) ((\y.y) z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_function_body_before_argument() {
+        assert_steps_to(r"(\x.x) ((\y.y) z)", r"(\x.(\a.a) x) ((\y.y) z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_church_numeral_addition() {
+        assert_evaluates_to(
+            r"\s.\z.s (s (s z))",
+            r"(\m.\n.\s.\z. m s (n s z)) (\s.\z.s (s z)) (\s.\z.s z)"
+        );
+    }
+
+    #[test]
+    pub fn test_agrees_with_normal() {
+        assert_agrees_when_both_terminate(Normal::new(), Applicative::new());
+    }
+
+    /// The leftmost innermost redex, found by recursion
+    fn defined_step(term: &Term) -> Option<Term> {
+        match term {
+            Term::Variable(_) => {
+                None
+            },
+            Term::Abstraction(name, body) => {
+                defined_step(body).map(|body| Term::Abstraction(name.clone(), box body))
+            },
+            Term::Application(t1, t2) if !t1.is_normal_form() => {
+                defined_step(t1).map(|t1| Term::Application(box t1, t2.clone()))
+            },
+            Term::Application(t1, t2) if !t2.is_normal_form() => {
+                defined_step(t2).map(|t2| Term::Application(t1.clone(), box t2))
+            },
+            Term::Application(box Term::Abstraction(name, body), t2) => {
+                Some(body.substitute(name.as_str(), t2))
+            },
+            Term::Application(_, _) => {
+                None
+            },
+        }
+    }
+
+    #[test]
+    pub fn test_steps_as_defined() {
+        assert_steps_as_defined(Applicative::new(), defined_step);
+    }
+}
@@ -0,0 +1,75 @@
+//! Beta-eta normal order reduction strategy
+//!
+//! This strategy performs the same beta reductions as the normal order strategy, and then
+//! contracts eta redexes (`λx.t x` where `x` isn't free in `t`) until the term is in beta-eta
+//! normal form. Eta reduction never creates beta redexes in a beta normal form, so the eta steps
+//! can safely be left until the end.
+use crate::eta::Eta;
+use crate::notation::named::Term;
+
+use super::{Evaluable, Normal};
+
+#[derive(Default)]
+pub struct BetaEta;
+
+impl BetaEta {
+    pub fn new() -> BetaEta {
+        BetaEta {}
+    }
+}
+
+impl Evaluable for BetaEta {
+    type Term = Term;
+    type Context = super::EmptyContext;
+
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        Normal::new().step_in_place(ctx, term) || term.eta_step_in_place()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_one;
+    use crate::evaluation::EmptyContext;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        assert_eq!(
+            parse_one(expected).unwrap(),
+            BetaEta::new().evaluate(&mut EmptyContext{}, parse_one(expr).unwrap())
+        )
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_evaluates_simple_application() {
+        assert_evaluates_to(r"x", r"(\y.y) x");
+    }
+
+    #[test]
+    pub fn test_eta_reduces_result() {
+        assert_evaluates_to(r"z", r"\x.((\y.y) z) x");
+    }
+
+    #[test]
+    pub fn test_eta_reduces_church_numeral_one() {
+        assert_evaluates_to(r"\s.s", r"(\n.\s.\z.s (n s z)) (\s.\z.z)");
+    }
+
+    #[test]
+    pub fn test_does_not_eta_reduce_when_bound_variable_is_free() {
+        assert_evaluates_to(r"\x.x x", r"\x.(\y.y) x x");
+    }
+}
@@ -0,0 +1,310 @@
+//! Call by need reduction strategy
+//!
+//! This strategy reduces the same redexes as the lazy (call by name) strategy, but arguments are
+//! shared instead of being copied. An argument that isn't already a value or a variable is moved
+//! into a heap, and the abstraction body refers to it through a heap variable. Evaluating a heap
+//! variable updates the heap, so the work is done at most once for all of its occurrences.
+use std::{
+    collections::HashMap,
+};
+
+use crate::notation::Notation;
+use crate::notation::named::Term;
+use crate::substitution::Substitutable;
+use crate::vars::Variables;
+
+use super::Evaluable;
+
+/// Prefix for heap variable names, which can't appear in parsed terms
+const HEAP_PREFIX: &str = "#";
+
+#[derive(Default)]
+pub struct CallByNeed;
+
+/// The shared arguments of a call by need evaluation
+pub struct Heap {
+    thunks: HashMap<String, Term>,
+}
+
+impl Default for Heap {
+    fn default() -> Heap {
+        Heap::new()
+    }
+}
+
+impl Heap {
+    pub fn new() -> Heap {
+        Heap {
+            thunks: HashMap::new(),
+        }
+    }
+
+    /// Returns a term that refers to `term` without duplicating any work that evaluating it
+    /// would do.
+    fn share(&mut self, term: Term) -> Term {
+        match term {
+            Term::Variable(_) | Term::Abstraction(_, _) => {
+                term
+            },
+            _ => {
+                let name = format!("{}{}", HEAP_PREFIX, self.thunks.len());
+                self.thunks.insert(name.clone(), term);
+                Term::Variable(name)
+            },
+        }
+    }
+
+    /// Returns the value a heap variable refers to, if it has been evaluated to one. A thunk can
+    /// evaluate to another heap variable, so this follows the chain to the end.
+    fn value(&self, name: &str) -> Option<&Term> {
+        let mut thunk = self.thunks.get(name)?;
+        loop {
+            match thunk {
+                Term::Abstraction(_, _) => return Some(thunk),
+                Term::Variable(name) if self.thunks.contains_key(name) => thunk = &self.thunks[name],
+                _ => return None,
+            }
+        }
+    }
+
+    /// Returns `term` with every heap variable replaced by the term it refers to
+    pub fn read_back(&self, term: &Term) -> Term {
+        term.free_variables()
+            .into_iter()
+            .filter_map(|name| self.thunks.get(&name).map(|thunk| (name, thunk)))
+            .fold(term.clone(), |term, (name, thunk)| term.substitute(name.as_str(), &self.read_back(thunk)))
+    }
+}
+
+impl CallByNeed {
+    pub fn new() -> CallByNeed {
+        CallByNeed {}
+    }
+}
+
+impl Evaluable for CallByNeed {
+    type Term = Term;
+    type Context = Heap;
+
+    fn step(&self, heap: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(heap, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, heap: &mut Self::Context, term: &mut Self::Term) -> bool {
+        // Evaluating a heap variable steps the shared thunk, rather than this occurrence. The thunk
+        // is moved out of the heap while it's stepped, rather than copied, and put back after. A
+        // placeholder keeps its entry, since the number of entries names new thunks.
+        let mut forced: Vec<(String, Term)> = Vec::new();
+        let stepped = loop {
+            let focus = match forced.last_mut() {
+                Some((_, thunk)) => thunk,
+                None => &mut *term,
+            };
+            match next_step(heap, focus) {
+                Step::Contract(depth) => {
+                    break contract(heap, function_at(focus, depth));
+                },
+                Step::Force(var) => {
+                    let thunk = heap.thunks.get_mut(&var).expect("forced variables are in the heap").take();
+                    forced.push((var, thunk));
+                },
+                Step::Stuck => {
+                    break false;
+                },
+            }
+        };
+        while let Some((var, thunk)) = forced.pop() {
+            heap.thunks.insert(var, thunk);
+        }
+        stepped
+    }
+
+    fn read_back(&self, heap: &Self::Context, term: Self::Term) -> Self::Term {
+        heap.read_back(&term)
+    }
+}
+
+/// What a step of a term does, found by walking down its spine of functions
+enum Step {
+    /// Contracts the application this many functions down the spine
+    Contract(usize),
+
+    /// Steps the thunk of the heap variable at the bottom of the spine
+    Force(String),
+
+    /// There's no step to take
+    Stuck,
+}
+
+fn next_step(heap: &Heap, mut term: &Term) -> Step {
+    let mut depth = 0;
+    loop {
+        term = match term {
+            Term::Application(box Term::Abstraction(_, _), _) => {
+                return Step::Contract(depth);
+            },
+            Term::Application(box Term::Variable(var), _) if heap.value(var).is_some() => {
+                return Step::Contract(depth);
+            },
+            Term::Application(t1, _) => {
+                depth += 1;
+                t1
+            },
+            Term::Variable(var) if heap.thunks.contains_key(var) && heap.value(var).is_none() => {
+                return Step::Force(var.clone());
+            },
+            _ => {
+                return Step::Stuck;
+            },
+        };
+    }
+}
+
+/// Returns the application `depth` functions down the spine of `term`
+fn function_at(mut term: &mut Term, depth: usize) -> &mut Term {
+    for _ in 0..depth {
+        term = term.function_mut().expect("the spine is this deep");
+    }
+    term
+}
+
+/// Contracts an application of an abstraction, or of a heap variable evaluated to one, sharing its
+/// argument. The abstraction's body is substituted into where it is, rather than copied out.
+fn contract(heap: &mut Heap, application: &mut Term) -> bool {
+    let result = match application {
+        Term::Application(box Term::Abstraction(name, body), arg) => {
+            body.substitute(name.as_str(), &heap.share(arg.take()))
+        },
+        Term::Application(box Term::Variable(var), arg) => {
+            let arg = heap.share(arg.take());
+            match heap.value(var) {
+                Some(Term::Abstraction(name, body)) => body.substitute(name.as_str(), &arg),
+                _ => unreachable!("heap values are abstractions"),
+            }
+        },
+        _ => {
+            return false;
+        },
+    };
+    *application = result;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_one;
+    use crate::evaluation::{EmptyContext, Lazy, Normal};
+    use crate::evaluation::testing::{small_step, Random};
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        let result = CallByNeed::new().evaluate(&mut Heap::new(), parse_one(expr).unwrap());
+        assert!(
+            parse_one(expected).unwrap().is_alpha_equivalent(&result),
+            "expected {}, but found {}", expected, result
+        );
+    }
+
+    fn assert_steps(lazy_steps: usize, need_steps: usize, expr: &str) {
+        let term = parse_one(expr).unwrap();
+        assert_eq!(
+            (lazy_steps, need_steps),
+            (
+                Lazy::new().evaluate_counting_steps(&mut EmptyContext{}, term.clone()).1,
+                CallByNeed::new().evaluate_counting_steps(&mut Heap::new(), term).1,
+            )
+        );
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_inside_abstraction() {
+        assert_evaluates_to(r"\x.(\y.y) z", r"\x.(\y.y) z");
+    }
+
+    #[test]
+    pub fn test_evaluates_simple_application() {
+        assert_evaluates_to(r"x", r"(\y.y) x");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_application_with_variable_on_left() {
+        assert_evaluates_to(r"z (\x.x) (\z.z)", r"z (\x.x) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_application_fully() {
+        assert_evaluates_to(r"z \z.z", r"((\x.x) z) \z.z");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_unused_argument() {
+        assert_evaluates_to(r"\z.z", r"(\x.\z.z) ((\x.x x) (\x.x x))");
+    }
+
+    #[test]
+    pub fn test_reads_back_unevaluated_argument_without_capture() {
+        assert_evaluates_to(r"\a.(\b.b) y", r"(\x.\y.x) ((\b.b) y)");
+    }
+
+    #[test]
+    pub fn test_reads_back_evaluated_argument() {
+        assert_evaluates_to(r"\y.\b.b", r"(\x.x (\y.x)) ((\a.a) \b.b)");
+    }
+
+    #[test]
+    pub fn test_shares_argument_evaluation() {
+        assert_steps(4, 3, r"(\x.x x) ((\y.y) (\z.z))");
+    }
+
+    #[test]
+    pub fn test_shares_argument_evaluation_across_many_uses() {
+        assert_steps(8, 5, r"(\x.x x x x) ((\y.y) (\z.z))");
+    }
+
+    #[test]
+    pub fn test_follows_thunk_evaluated_to_heap_variable() {
+        // The argument's thunk evaluates to the thunk of its own argument
+        assert_evaluates_to("z", r"(\x. x z) ((\a.a) ((\b.b) (\c.c)))");
+    }
+
+    #[test]
+    pub fn test_agrees_with_lazy() {
+        // Arguments are read back as far as they were evaluated, so the results are compared by
+        // their normal forms, where those can be found
+        let mut random = Random::new(0x5eed_1234_abcd_ef01);
+        let mut checked = 0;
+        for _ in 0..2000 {
+            let term = random.term(6, &mut Vec::new());
+            if let Some(expected) = small_step(&Lazy::new(), term.clone()) {
+                let result = CallByNeed::new().evaluate(&mut Heap::new(), term.clone());
+                assert!(result.is_whnf(), "{} evaluated to {}", term, result);
+                if let (Some(expected), Some(result)) = (small_step(&Normal::new(), expected), small_step(&Normal::new(), result.clone())) {
+                    assert!(
+                        expected.is_alpha_equivalent(&result),
+                        "{} evaluated to {}, but lazy evaluation gave {}", term, result, expected
+                    );
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 1000);
+    }
+
+    #[test]
+    pub fn test_forcing_heap_variable_steps_shared_thunk() {
+        let mut heap = Heap::new();
+        let mut term = heap.share(parse_one(r"(\x.x) (\y.y)").unwrap());
+        assert!(CallByNeed::new().step_in_place(&mut heap, &mut term));
+        assert_eq!(Term::Variable("#0".to_string()), term);
+        assert_eq!(Some(&parse_one(r"\y.y").unwrap()), heap.value("#0"));
+    }
+}
@@ -3,10 +3,11 @@
 //! This strategy evaluates the outermost terms, but only after the right-hand side has been evaluated
 //! to a value (abstractions in a simple calculus).
 use crate::notation::named::Term;
-use crate::substitution::Substitutable;
+use crate::path::{contract_in_place, Dir};
 
 use super::Evaluable;
 
+#[derive(Default)]
 pub struct CallByValue;
 
 impl CallByValue {
@@ -19,23 +20,43 @@ impl Evaluable for CallByValue {
     type Term = Term;
     type Context = super::EmptyContext;
 
-    fn step(&self, ctx: &mut Self::Context, term: Self::Term) -> Option<Self::Term> {
-        match term {
-            Term::Application(box Term::Abstraction(name, body), box arg) if arg.is_value() => {
-                Some(body.substitute(name.as_str(), &arg))
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        match next_redex(term) {
+            Some(path) => contract_in_place(term, &path),
+            None => false,
+        }
+    }
+}
+
+/// Returns the path to the redex call by value contracts next, or `None` if the term is a value or
+/// is stuck. The term is followed down to the redex, rather than recursing.
+pub(super) fn next_redex(mut term: &Term) -> Option<Vec<Dir>> {
+    let mut path = Vec::new();
+    loop {
+        term = match term {
+            Term::Application(box Term::Abstraction(_, _), arg) if arg.is_value() => {
+                return Some(path);
             },
-            Term::Application(t1, box t2) if t1.is_value() => {
-                self.step(ctx, t2)
-                    .map(|t2_evaluated| Term::Application(t1, box t2_evaluated))
+            Term::Application(t1, t2) if t1.is_value() => {
+                path.push(Dir::Arg);
+                t2
             },
-            Term::Application(box t1, t2) => {
-                self.step(ctx, t1)
-                    .map(|t1_evaluated| Term::Application(box t1_evaluated, t2))
+            Term::Application(t1, _) => {
+                path.push(Dir::Fun);
+                t1
             },
             _ => {
-                None
+                return None;
             },
-        }
+        };
     }
 }
 
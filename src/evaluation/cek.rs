@@ -0,0 +1,146 @@
+//! CEK machine
+//!
+//! An abstract machine for call by value evaluation, with a control term, an environment, and a
+//! continuation describing what to do with the value of the control term. It gives the same
+//! results as the `CallByValue` strategy.
+use crate::notation::{DeBruijn, Named};
+
+use super::machine::{self, Closure, Environment};
+
+#[derive(Default)]
+pub struct Cek;
+
+enum Value<'t> {
+    /// A closure whose term is an abstraction
+    Closure(Closure<'t>),
+
+    /// A term that can't be evaluated any further, because it depends on a free variable
+    Stuck(DeBruijn),
+}
+
+enum Continuation<'t> {
+    /// Evaluate this argument once the function has been evaluated
+    Argument(Closure<'t>),
+
+    /// Apply this function once the argument has been evaluated
+    Function(Closure<'t>),
+}
+
+impl Cek {
+    pub fn new() -> Cek {
+        Cek {}
+    }
+
+    /// Evaluate the given term as much as possible
+    pub fn evaluate(&self, term: &Named) -> Named {
+        machine::run_named(term, |t| self.run(t))
+    }
+
+    /// Evaluate the given De Bruijn term as much as possible
+    pub fn run(&self, term: &DeBruijn) -> DeBruijn {
+        let mut control = Closure::new(term, Environment::empty());
+        let mut continuations: Vec<Continuation<'_>> = Vec::new();
+
+        loop {
+            let value = match control.term {
+                DeBruijn::Application(t1, t2) => {
+                    continuations.push(Continuation::Argument(Closure::new(t2, control.env.clone())));
+                    control = Closure::new(t1, control.env);
+                    continue;
+                },
+                DeBruijn::Abstraction(_) => {
+                    Value::Closure(control)
+                },
+                DeBruijn::Variable(index) => {
+                    match control.env.get(*index) {
+                        Some(bound) => Value::Closure(bound.clone()),
+                        None => Value::Stuck(DeBruijn::Variable(index - control.env.len())),
+                    }
+                },
+            };
+
+            control = match (value, continuations.pop()) {
+                (Value::Closure(function), Some(Continuation::Argument(arg))) => {
+                    continuations.push(Continuation::Function(function));
+                    arg
+                },
+                (Value::Closure(arg), Some(Continuation::Function(function))) => {
+                    match function.term {
+                        DeBruijn::Abstraction(body) => Closure::new(body, function.env.bind(arg)),
+                        _ => unreachable!("function values are always abstractions"),
+                    }
+                },
+                (Value::Closure(closure), None) => {
+                    return closure.read_back();
+                },
+                (Value::Stuck(term), continuation) => {
+                    // Nothing around a stuck term can be evaluated, so the remaining continuations
+                    // are read back as they are
+                    return continuation
+                        .into_iter()
+                        .chain(continuations.into_iter().rev())
+                        .fold(term, |term, continuation| match continuation {
+                            Continuation::Argument(arg) => DeBruijn::Application(box term, box arg.read_back()),
+                            Continuation::Function(function) => DeBruijn::Application(box function.read_back(), box term),
+                        });
+                },
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::CallByValue;
+    use crate::evaluation::testing::assert_agrees_with_small_step;
+    use crate::parser::parse_one;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        let result = Cek::new().evaluate(&parse_one(expr).unwrap());
+        assert!(
+            parse_one(expected).unwrap().is_alpha_equivalent(&result),
+            "expected {}, but found {}", expected, result
+        );
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_inside_abstraction() {
+        assert_evaluates_to(r"\x.(\y.y) z", r"\x.(\y.y) z");
+    }
+
+    #[test]
+    pub fn test_evaluates_simple_application() {
+        assert_evaluates_to(r"\z.z", r"(\y.y) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_application_without_a_value_argument() {
+        assert_evaluates_to(r"(\y.y) x", r"(\y.y) x");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_around_stuck_argument() {
+        assert_evaluates_to(r"(\a.a) ((\y.y) x) ((\b.b) \c.c)", r"(\a.a) ((\y.y) x) ((\b.b) \c.c)");
+    }
+
+    #[test]
+    pub fn test_evaluates_three_argument_application() {
+        assert_evaluates_to(r"\z.z", r"(\x.\y.\z. x y z) (\x.x) (\y.y) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_with_and_tru_fls() {
+        assert_evaluates_to(r"\t.\f.f", r"(\a.\b.(a b) (\t.\f.f)) (\t.\f.t) (\t.\f.f)");
+    }
+
+    #[test]
+    pub fn test_agrees_with_call_by_value() {
+        assert_agrees_with_small_step(CallByValue::new(), |t| Cek::new().evaluate(t));
+    }
+}
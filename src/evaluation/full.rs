@@ -2,10 +2,11 @@
 //!
 //! This strategy can evaluate any term at any point.
 use crate::notation::named::Term;
-use crate::substitution::Substitutable;
+use crate::path::{contract_in_place, Dir};
 
 use super::Evaluable;
 
+#[derive(Default)]
 pub struct Full;
 
 impl Full {
@@ -18,28 +19,65 @@ impl Evaluable for Full {
     type Term = Term;
     type Context = super::EmptyContext;
 
-    fn step(&self, ctx: &mut Self::Context, term: Self::Term) -> Option<Self::Term> {
-        match term {
-            Term::Abstraction(arg, box body) => {
-                self.step(ctx, body)
-                    .map(|body_evaluated| Term::Abstraction(arg, box body_evaluated))
-            },
-            Term::Application(box t1, t2) if t1.is_redex() => {
-                self.step(ctx, t1)
-                    .map(|t1_evaluated| Term::Application(box t1_evaluated, t2))
-            },
-            Term::Application(t1, box t2) if t2.is_redex() => {
-                self.step(ctx, t2)
-                    .map(|t2_evaluated| Term::Application(t1, box t2_evaluated))
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        match next_redex(term) {
+            Some(path) => contract_in_place(term, &path),
+            None => false,
+        }
+    }
+}
+
+/// Returns the path to the redex to contract next: the first application, after any applications
+/// inside it, that is a redex. Only the outermost abstractions are evaluated inside.
+///
+/// The search keeps its own stack, so it handles terms nested millions of levels deep.
+fn next_redex(term: &Term) -> Option<Vec<Dir>> {
+    enum Work<'t> {
+        Enter(&'t Term, Option<Dir>),
+        Exit(&'t Term),
+    }
+
+    let mut path = Vec::new();
+    let mut focus = term;
+    while let Term::Abstraction(_, body) = focus {
+        path.push(Dir::Body);
+        focus = body;
+    }
+
+    // Only applications are entered. The root is the last to be exited, so whatever it pops off
+    // the path no longer matters by then.
+    let mut work = vec![Work::Enter(focus, None)];
+    while let Some(item) = work.pop() {
+        match item {
+            Work::Enter(term, dir) => {
+                if let Term::Application(t1, t2) = term {
+                    path.extend(dir);
+                    work.push(Work::Exit(term));
+                    if let Term::Application(_, _) = **t2 {
+                        work.push(Work::Enter(t2, Some(Dir::Arg)));
+                    }
+                    if let Term::Application(_, _) = **t1 {
+                        work.push(Work::Enter(t1, Some(Dir::Fun)));
+                    }
+                }
             },
-            Term::Application(box Term::Abstraction(name, body), box arg) => {
-                Some(body.substitute(name.as_str(), &arg))
+            Work::Exit(Term::Application(box Term::Abstraction(_, _), _)) => {
+                return Some(path);
             },
-            _ => {
-                None
+            Work::Exit(_) => {
+                path.pop();
             },
         }
     }
+    None
 }
 
 #[cfg(test)]
@@ -0,0 +1,96 @@
+//! Head reduction strategy
+//!
+//! This strategy only reduces the redex at the head of the term, including under abstractions,
+//! and stops as soon as the term is in head normal form: `λx1...λxn.y t1 ... tm`. A term has a
+//! head normal form exactly when it is solvable, and the arguments `t1 ... tm` are where the
+//! branches of its Böhm tree start.
+use crate::notation::named::Term;
+use crate::path::contract_in_place;
+
+use super::Evaluable;
+
+#[derive(Default)]
+pub struct HeadNormal;
+
+impl HeadNormal {
+    pub fn new() -> HeadNormal {
+        HeadNormal {}
+    }
+}
+
+impl Evaluable for HeadNormal {
+    type Term = Term;
+    type Context = super::EmptyContext;
+
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, mut term: &mut Self::Term) -> bool {
+        // The head redex is at the bottom of the abstractions and the spine of functions under
+        // them, which are walked down without recursing
+        loop {
+            term = match term {
+                Term::Abstraction(_, body) => {
+                    &mut **body
+                },
+                Term::Application(box Term::Abstraction(_, _), _) => {
+                    return contract_in_place(term, &[]);
+                },
+                Term::Application(t1, _) => {
+                    &mut **t1
+                },
+                Term::Variable(_) => {
+                    return false;
+                },
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_one;
+    use crate::evaluation::EmptyContext;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        let result = HeadNormal::new().evaluate(&mut EmptyContext{}, parse_one(expr).unwrap());
+        assert!(result.is_head_normal_form());
+        assert_eq!(parse_one(expected).unwrap(), result);
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_evaluates_head_redex_inside_abstraction() {
+        assert_evaluates_to(r"\x.z", r"\x.(\y.y) z");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_arguments_of_head_variable() {
+        assert_evaluates_to(r"\x.x ((\y.y) z)", r"\x.x ((\y.y) z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_head_redex_of_application() {
+        assert_evaluates_to(r"\a.z ((\x.x) y)", r"\a.(\b.b z) (\c.c) ((\x.x) y)");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_divergent_argument() {
+        assert_evaluates_to(r"\x.x ((\x.x x) (\x.x x))", r"\x.x ((\x.x x) (\x.x x))");
+    }
+
+    #[test]
+    pub fn test_evaluates_abstraction_result_of_head_redex() {
+        assert_evaluates_to(r"\y.y", r"(\x.x) (\y.(\z.z) y)");
+    }
+}
@@ -0,0 +1,126 @@
+//! Head spine reduction strategy
+//!
+//! From Sestoft's "Demonstrating Lambda Calculus Reduction". Like head reduction it stops at a
+//! head normal form, but the function of a redex is reduced to head normal form (including under
+//! its abstractions) before the redex is contracted.
+use crate::notation::named::Term;
+use crate::path::contract_in_place;
+
+use super::Evaluable;
+
+#[derive(Default)]
+pub struct HeadSpine;
+
+impl HeadSpine {
+    pub fn new() -> HeadSpine {
+        HeadSpine {}
+    }
+}
+
+impl Evaluable for HeadSpine {
+    type Term = Term;
+    type Context = super::EmptyContext;
+
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, mut term: &mut Self::Term) -> bool {
+        // Walks down the abstractions and the spine of functions without recursing. A spine with
+        // a variable at the bottom is already in head normal form.
+        loop {
+            let contract = match term {
+                Term::Application(t1, _) => t1.is_value() && t1.is_head_normal_form(),
+                _ => false,
+            };
+            if contract {
+                return contract_in_place(term, &[]);
+            }
+            term = match term {
+                Term::Abstraction(_, body) => {
+                    &mut **body
+                },
+                Term::Application(t1, _) => {
+                    &mut **t1
+                },
+                Term::Variable(_) => {
+                    return false;
+                },
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_one;
+    use crate::evaluation::EmptyContext;
+    use crate::evaluation::testing::assert_steps_as_defined;
+    use crate::substitution::Substitutable;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        let result = HeadSpine::new().evaluate(&mut EmptyContext{}, parse_one(expr).unwrap());
+        assert!(result.is_head_normal_form());
+        assert_eq!(parse_one(expected).unwrap(), result);
+    }
+
+    fn assert_steps_to(expected: &str, expr: &str) {
+        assert_eq!(
+            Some(parse_one(expected).unwrap()),
+            HeadSpine::new().step(&mut EmptyContext{}, parse_one(expr).unwrap())
+        )
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_evaluates_head_redex_inside_abstraction() {
+        assert_evaluates_to(r"\x.z", r"\x.(\y.y) z");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_arguments_of_head_variable() {
+        assert_evaluates_to(r"\x.x ((\y.y) z)", r"\x.x ((\y.y) z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_function_before_contracting() {
+        assert_steps_to(r"(\x.x) ((\y.y) z)", r"(\x.(\a.a) x) ((\y.y) z)");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_argument_before_contracting() {
+        assert_steps_to(r"(\y.y) z", r"(\x.x) ((\y.y) z)");
+    }
+
+    /// Sestoft's definition, stepping into subterms by recursion
+    fn defined_step(term: &Term) -> Option<Term> {
+        match term {
+            Term::Abstraction(name, body) => {
+                defined_step(body).map(|body| Term::Abstraction(name.clone(), box body))
+            },
+            Term::Application(t1, t2) if !t1.is_head_normal_form() => {
+                defined_step(t1).map(|t1| Term::Application(box t1, t2.clone()))
+            },
+            Term::Application(box Term::Abstraction(name, body), t2) => {
+                Some(body.substitute(name.as_str(), t2))
+            },
+            _ => {
+                None
+            },
+        }
+    }
+
+    #[test]
+    pub fn test_steps_as_defined() {
+        assert_steps_as_defined(HeadSpine::new(), defined_step);
+    }
+}
@@ -0,0 +1,203 @@
+//! Hybrid applicative order reduction strategy
+//!
+//! From Sestoft's "Demonstrating Lambda Calculus Reduction". Like applicative order, arguments are
+//! reduced to normal form before a redex is contracted, but the function is only reduced to a
+//! value with call by value. This avoids reducing under an abstraction that will be applied, which
+//! makes it a better model of how a strict functional language does partial evaluation.
+use std::iter;
+
+use crate::notation::named::Term;
+use crate::path::{contract_in_place, Dir};
+
+use super::{call_by_value, Evaluable};
+
+#[derive(Default)]
+pub struct HybridApplicative;
+
+impl HybridApplicative {
+    pub fn new() -> HybridApplicative {
+        HybridApplicative {}
+    }
+}
+
+impl Evaluable for HybridApplicative {
+    type Term = Term;
+    type Context = super::EmptyContext;
+
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        match next_redex(term) {
+            Some(path) => contract_in_place(term, &path),
+            None => false,
+        }
+    }
+}
+
+/// Returns the path to the redex to contract next, searching with an explicit stack so that deep
+/// terms don't overflow.
+///
+/// The function of an application is first reduced with call by value. Once call by value is stuck
+/// on it, it's stuck on every function further down the same spine, so the rest of the spine is
+/// searched without trying it again. A redex is contracted once nothing in its argument is.
+fn next_redex(term: &Term) -> Option<Vec<Dir>> {
+    enum Work<'t> {
+        Enter(&'t Term, usize, Option<Dir>),
+        Exit(usize),
+    }
+
+    let mut path = Vec::new();
+    let mut work = vec![Work::Enter(term, 0, None)];
+    while let Some(item) = work.pop() {
+        match item {
+            Work::Enter(term, parent_length, dir) => {
+                path.truncate(parent_length);
+                path.extend(dir);
+                match term {
+                    Term::Variable(_) => {},
+                    Term::Abstraction(_, body) => {
+                        work.push(Work::Enter(body, path.len(), Some(Dir::Body)));
+                    },
+                    Term::Application(t1, _) => {
+                        if let Some(redex) = call_by_value::next_redex(t1) {
+                            path.push(Dir::Fun);
+                            path.extend(redex);
+                            return Some(path);
+                        }
+
+                        // The arguments of the spine, last first
+                        let mut focus = term;
+                        let mut arguments = Vec::new();
+                        while let Term::Application(t1, t2) = focus {
+                            arguments.push(&**t2);
+                            focus = t1;
+                        }
+                        let length = path.len();
+                        path.extend(iter::repeat_n(Dir::Fun, arguments.len() - 1));
+
+                        if let Term::Abstraction(_, _) = focus {
+                            // The redex at the bottom of the spine is contracted once its argument
+                            // is in normal form
+                            work.push(Work::Exit(path.len()));
+                            work.push(Work::Enter(arguments[arguments.len() - 1], path.len(), Some(Dir::Arg)));
+                        } else {
+                            // A variable applied to arguments, which are reduced in turn from the
+                            // left
+                            for (depth, argument) in arguments.into_iter().enumerate() {
+                                work.push(Work::Enter(argument, length + depth, Some(Dir::Arg)));
+                            }
+                        }
+                    },
+                }
+            },
+            Work::Exit(length) => {
+                path.truncate(length);
+                return Some(path);
+            },
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_one;
+    use crate::evaluation::{CallByValue, EmptyContext, Normal};
+    use crate::evaluation::testing::{assert_agrees_when_both_terminate, assert_steps_as_defined};
+    use crate::substitution::Substitutable;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        assert_eq!(
+            parse_one(expected).unwrap(),
+            HybridApplicative::new().evaluate(&mut EmptyContext{}, parse_one(expr).unwrap())
+        )
+    }
+
+    fn assert_steps_to(expected: &str, expr: &str) {
+        assert_eq!(
+            Some(parse_one(expected).unwrap()),
+            HybridApplicative::new().step(&mut EmptyContext{}, parse_one(expr).unwrap())
+        )
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_abstraction() {
+        assert_evaluates_to(r"\x.z x", r"\x.((\y.y) z) x");
+    }
+
+    #[test]
+    pub fn test_evaluates_argument_before_contracting() {
+        assert_steps_to(r"(\x.x x) z", r"(\x.x x) ((\y.y) z)");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_inside_function_before_contracting() {
+        assert_steps_to(r"(\a.a) z", r"(\x.(\a.a) x) z");
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_stuck_function() {
+        assert_steps_to(r"x z ((\y.y) z)", r"x ((\a.a) z) ((\y.y) z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_church_numeral_addition() {
+        assert_evaluates_to(
+            r"\s.\z.s (s (s z))",
+            r"(\m.\n.\s.\z. m s (n s z)) (\s.\z.s (s z)) (\s.\z.s z)"
+        );
+    }
+
+    #[test]
+    pub fn test_agrees_with_normal() {
+        assert_agrees_when_both_terminate(Normal::new(), HybridApplicative::new());
+    }
+
+    /// Sestoft's definition, stepping into subterms by recursion
+    fn defined_step(term: &Term) -> Option<Term> {
+        match term {
+            Term::Variable(_) => {
+                None
+            },
+            Term::Abstraction(name, body) => {
+                defined_step(body).map(|body| Term::Abstraction(name.clone(), box body))
+            },
+            Term::Application(t1, t2) => {
+                if let Some(t1) = CallByValue::new().step(&mut EmptyContext{}, (**t1).clone()) {
+                    return Some(Term::Application(box t1, t2.clone()));
+                }
+                match &**t1 {
+                    Term::Abstraction(name, body) if t2.is_normal_form() => {
+                        Some(body.substitute(name.as_str(), t2))
+                    },
+                    Term::Abstraction(_, _) => {
+                        defined_step(t2).map(|t2| Term::Application(t1.clone(), box t2))
+                    },
+                    _ if !t1.is_normal_form() => {
+                        defined_step(t1).map(|t1| Term::Application(box t1, t2.clone()))
+                    },
+                    _ => {
+                        defined_step(t2).map(|t2| Term::Application(t1.clone(), box t2))
+                    },
+                }
+            },
+        }
+    }
+
+    #[test]
+    pub fn test_steps_as_defined() {
+        assert_steps_as_defined(HybridApplicative::new(), defined_step);
+    }
+}
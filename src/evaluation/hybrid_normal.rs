@@ -0,0 +1,142 @@
+//! Hybrid normal order reduction strategy
+//!
+//! From Sestoft's "Demonstrating Lambda Calculus Reduction". Like normal order it reduces terms to
+//! normal form, but the function of a redex is first reduced to head normal form with head spine
+//! reduction, rather than only to weak head normal form.
+use crate::notation::named::Term;
+
+use super::{Evaluable, HeadSpine};
+
+#[derive(Default)]
+pub struct HybridNormal;
+
+impl HybridNormal {
+    pub fn new() -> HybridNormal {
+        HybridNormal {}
+    }
+}
+
+impl Evaluable for HybridNormal {
+    type Term = Term;
+    type Context = super::EmptyContext;
+
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        // The subterms still to search, leftmost last. Keeping them on a stack rather than
+        // recursing lets deep terms be searched.
+        let mut work = vec![term];
+        while let Some(term) = work.pop() {
+            match term {
+                Term::Variable(_) => {},
+                Term::Abstraction(_, body) => {
+                    work.push(&mut **body);
+                },
+                Term::Application(_, _) if !term.has_variable_head() => {
+                    // There's a redex at the bottom of the spine, and its function is reduced to
+                    // head normal form before it's contracted
+                    return HeadSpine::new().step_in_place(ctx, term);
+                },
+                Term::Application(_, _) => {
+                    // A variable applied to arguments, which are reduced in turn from the left
+                    let mut focus = term;
+                    let mut arguments = Vec::new();
+                    while let Term::Application(t1, t2) = focus {
+                        arguments.push(&mut **t2);
+                        focus = &mut **t1;
+                    }
+                    work.extend(arguments);
+                },
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_one;
+    use crate::evaluation::{EmptyContext, Normal};
+    use crate::evaluation::testing::{assert_agrees_when_both_terminate, assert_steps_as_defined};
+    use crate::substitution::Substitutable;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        let result = HybridNormal::new().evaluate(&mut EmptyContext{}, parse_one(expr).unwrap());
+        assert!(result.is_normal_form());
+        assert_eq!(parse_one(expected).unwrap(), result);
+    }
+
+    fn assert_steps_to(expected: &str, expr: &str) {
+        assert_eq!(
+            Some(parse_one(expected).unwrap()),
+            HybridNormal::new().step(&mut EmptyContext{}, parse_one(expr).unwrap())
+        )
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_abstraction() {
+        assert_evaluates_to(r"\x.z x", r"\x.((\y.y) z) x");
+    }
+
+    #[test]
+    pub fn test_evaluates_application_with_variable_on_left() {
+        assert_evaluates_to(r"z (\x.x) (\z.z)", r"z ((\y.y) \x.x) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_function_before_contracting() {
+        assert_steps_to(r"(\x.x) ((\y.y) z)", r"(\x.(\a.a) x) ((\y.y) z)");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_divergent_argument() {
+        assert_evaluates_to(r"z", r"(\x.z) ((\x.x x) (\x.x x))");
+    }
+
+    #[test]
+    pub fn test_agrees_with_normal() {
+        assert_agrees_when_both_terminate(Normal::new(), HybridNormal::new());
+    }
+
+    /// Sestoft's definition, stepping into subterms by recursion
+    fn defined_step(term: &Term) -> Option<Term> {
+        match term {
+            Term::Variable(_) => {
+                None
+            },
+            Term::Abstraction(name, body) => {
+                defined_step(body).map(|body| Term::Abstraction(name.clone(), box body))
+            },
+            Term::Application(t1, t2) if !t1.is_head_normal_form() => {
+                HeadSpine::new().step(&mut EmptyContext{}, (**t1).clone())
+                    .map(|t1| Term::Application(box t1, t2.clone()))
+            },
+            Term::Application(box Term::Abstraction(name, body), t2) => {
+                Some(body.substitute(name.as_str(), t2))
+            },
+            Term::Application(t1, t2) if !t1.is_normal_form() => {
+                defined_step(t1).map(|t1| Term::Application(box t1, t2.clone()))
+            },
+            Term::Application(t1, t2) => {
+                defined_step(t2).map(|t2| Term::Application(t1.clone(), box t2))
+            },
+        }
+    }
+
+    #[test]
+    pub fn test_steps_as_defined() {
+        assert_steps_as_defined(HybridNormal::new(), defined_step);
+    }
+}
@@ -0,0 +1,108 @@
+//! Krivine machine
+//!
+//! An abstract machine for call by name evaluation. Arguments are pushed onto a stack as
+//! unevaluated closures, and popped into the environment when an abstraction is reached. It
+//! gives the same results as the `Lazy` strategy.
+use crate::notation::{DeBruijn, Named};
+
+use super::machine::{self, Closure, Environment};
+
+#[derive(Default)]
+pub struct Krivine;
+
+impl Krivine {
+    pub fn new() -> Krivine {
+        Krivine {}
+    }
+
+    /// Evaluate the given term as much as possible
+    pub fn evaluate(&self, term: &Named) -> Named {
+        machine::run_named(term, |t| self.run(t))
+    }
+
+    /// Evaluate the given De Bruijn term as much as possible
+    pub fn run(&self, term: &DeBruijn) -> DeBruijn {
+        let mut closure = Closure::new(term, Environment::empty());
+        let mut stack: Vec<Closure<'_>> = Vec::new();
+
+        loop {
+            match closure.term {
+                DeBruijn::Application(t1, t2) => {
+                    stack.push(Closure::new(t2, closure.env.clone()));
+                    closure = Closure::new(t1, closure.env);
+                },
+                DeBruijn::Abstraction(body) => {
+                    match stack.pop() {
+                        Some(arg) => closure = Closure::new(body, closure.env.bind(arg)),
+                        None => return closure.read_back(),
+                    }
+                },
+                DeBruijn::Variable(index) => {
+                    match closure.env.get(*index) {
+                        Some(bound) => closure = bound.clone(),
+                        None => {
+                            // A free variable at the head, so the remaining arguments are left
+                            // unevaluated
+                            let head = DeBruijn::Variable(index - closure.env.len());
+                            return stack
+                                .iter()
+                                .rev()
+                                .fold(head, |app, arg| DeBruijn::Application(box app, box arg.read_back()));
+                        },
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::Lazy;
+    use crate::evaluation::testing::assert_agrees_with_small_step;
+    use crate::parser::parse_one;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        let result = Krivine::new().evaluate(&parse_one(expr).unwrap());
+        assert!(
+            parse_one(expected).unwrap().is_alpha_equivalent(&result),
+            "expected {}, but found {}", expected, result
+        );
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_evaluates_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_inside_abstraction() {
+        assert_evaluates_to(r"\x.(\y.y) z", r"\x.(\y.y) z");
+    }
+
+    #[test]
+    pub fn test_evaluates_simple_application() {
+        assert_evaluates_to(r"x", r"(\y.y) x");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_application_with_variable_on_left() {
+        assert_evaluates_to(r"z (\x.x) (\z.z)", r"z (\x.x) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_application_fully() {
+        assert_evaluates_to(r"z \z.z", r"((\x.x) z) \z.z");
+    }
+
+    #[test]
+    pub fn test_reads_back_unevaluated_argument() {
+        assert_evaluates_to(r"\y.(\a.a) b", r"(\x.\y.x) ((\a.a) b)");
+    }
+
+    #[test]
+    pub fn test_agrees_with_lazy() {
+        assert_agrees_with_small_step(Lazy::new(), |t| Krivine::new().evaluate(t));
+    }
+}
@@ -1,50 +1,79 @@
 //! Lazy (call by name) reduction strategy
 //!
-//! This strategy uses the normal order strategy, but does not evaluate within abstractions.
-use crate::notation::named::Term;
-use crate::substitution::Substitutable;
+//! This strategy uses the normal order strategy, but does not evaluate within abstractions. It
+//! only reduces the redex at the head of the term, and stops as soon as the term is in weak head
+//! normal form: an abstraction, or a variable applied to arguments. That makes it the weak head
+//! reduction strategy too, which is what `WeakHeadNormal` names it.
+//!
+//! It is written against the `Notation` trait, so it works on both named and De Bruijn terms.
+use std::marker::PhantomData;
+
+use crate::notation::{Named, Node, Notation};
 
 use super::Evaluable;
 
-pub struct Lazy;
+pub struct Lazy<T = Named> {
+    notation: PhantomData<T>,
+}
+
+/// Weak head reduction, which contracts the same redexes as lazy evaluation
+pub type WeakHeadNormal<T = Named> = Lazy<T>;
 
-impl Lazy {
-    pub fn new() -> Lazy {
-        Lazy {}
+impl<T> Default for Lazy<T> {
+    fn default() -> Lazy<T> {
+        Lazy::new()
     }
 }
 
-impl Evaluable for Lazy {
-    type Term = Term;
+impl<T> Lazy<T> {
+    pub fn new() -> Lazy<T> {
+        Lazy { notation: PhantomData }
+    }
+}
+
+impl<T: Notation> Evaluable for Lazy<T> {
+    type Term = T;
     type Context = super::EmptyContext;
 
-    fn step(&self, ctx: &mut Self::Context, term: Self::Term) -> Option<Self::Term> {
-        match term {
-            Term::Application(box Term::Abstraction(name, body), box arg) => {
-                Some(body.substitute(name.as_str(), &arg))
-            },
-            Term::Application(box t1, t2) if t1.is_redex() => {
-                self.step(ctx, t1)
-                    .map(|t1_evaluated| Term::Application(box t1_evaluated, t2))
-            },
-            _ => {
-                None
-            },
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, mut term: &mut Self::Term) -> bool {
+        // The redex is at the bottom of the spine of functions, which is walked down without
+        // recursing so that long spines can't overflow the stack
+        loop {
+            let contracted = match term.view() {
+                Node::Application(t1, t2) => match t1.view() {
+                    Node::Abstraction(binder, body) => T::instantiate(binder, body, t2),
+                    Node::Application(_, _) => {
+                        term = term.function_mut().expect("the term is an application");
+                        continue;
+                    },
+                    Node::Variable(_) => return false,
+                },
+                _ => return false,
+            };
+            *term = contracted;
+            return true;
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parser::parse_one;
+    use crate::parser::{parse_de_bruijn, parse_one};
     use crate::evaluation::EmptyContext;
     use super::*;
 
     fn assert_evaluates_to(expected: &str, expr: &str) {
-        assert_eq!(
-            parse_one(expected).unwrap(),
-            Lazy::new().evaluate(&mut EmptyContext{}, parse_one(expr).unwrap())
-        )
+        let result = Lazy::new().evaluate(&mut EmptyContext{}, parse_one(expr).unwrap());
+        assert!(result.is_whnf());
+        assert_eq!(parse_one(expected).unwrap(), result);
     }
 
     #[test]
@@ -91,4 +120,27 @@ mod tests {
     pub fn test_evaluates_application_fully2() {
         assert_evaluates_to(r"z \z.z", r"((\x.x) z) \z.z");
     }
+
+    #[test]
+    pub fn test_does_not_evaluate_arguments_of_variable() {
+        assert_evaluates_to(r"z ((\x.x) y)", r"z ((\x.x) y)");
+    }
+
+    #[test]
+    pub fn test_evaluates_head_redex() {
+        assert_evaluates_to(r"z ((\x.x) y)", r"(\a.a z) (\b.b) ((\x.x) y)");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_divergent_argument() {
+        assert_evaluates_to(r"\z.z", r"(\x.\z.z) ((\x.x x) (\x.x x))");
+    }
+
+    #[test]
+    pub fn test_evaluates_de_bruijn_term() {
+        let term = parse_de_bruijn(r"(\.\.1) 3 4").unwrap();
+        let result = WeakHeadNormal::new().evaluate(&mut EmptyContext{}, term);
+        assert!(result.is_whnf());
+        assert_eq!(parse_de_bruijn("3").unwrap(), result);
+    }
 }
@@ -0,0 +1,150 @@
+//! Normal order reduction on locally nameless terms
+//!
+//! This contracts the same redexes as the `Normal` strategy. Contracting a redex only opens the
+//! body of its abstraction, which can't capture anything, so no renaming is needed. To step inside
+//! an abstraction, its body is opened with a fresh free variable and closed over it again after.
+use crate::notation::locally_nameless::{Term, Variable};
+use crate::path::Dir;
+use crate::substitution::{FreshNames, Primes};
+
+use super::Evaluable;
+
+#[derive(Default)]
+pub struct LocallyNamelessNormal;
+
+impl LocallyNamelessNormal {
+    pub fn new() -> LocallyNamelessNormal {
+        LocallyNamelessNormal {}
+    }
+}
+
+impl Evaluable for LocallyNamelessNormal {
+    type Term = Term;
+    type Context = super::EmptyContext;
+
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
+        }
+    }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        let path = match leftmost_outermost_redex(term) {
+            Some(path) => path,
+            None => return false,
+        };
+
+        // Each abstraction on the way down to the redex is opened, and closed again on a second
+        // walk down once the redex is contracted. The names come from one supply that avoids the
+        // free names of the term, so they're distinct and closing from the outside in can't
+        // capture an inner name.
+        let mut fresh = FreshNames::new(&Primes, term.free_names());
+        let mut names = Vec::new();
+        let redex = follow(term, &path, |body| {
+            let name = fresh.fresh("x");
+            *body = body.open(&Term::Variable(Variable::Free(name.clone())));
+            names.push(name);
+        });
+        let contracted = match redex {
+            Term::Application(box Term::Abstraction(body), arg) => body.open(arg),
+            _ => unreachable!("the path leads to a redex"),
+        };
+        *redex = contracted;
+
+        let mut names = names.iter();
+        follow(term, &path, |body| {
+            *body = body.close(names.next().expect("every abstraction on the path was opened"));
+        });
+        true
+    }
+}
+
+/// Returns the path to the leftmost outermost redex, searching with an explicit stack
+fn leftmost_outermost_redex(term: &Term) -> Option<Vec<Dir>> {
+    let mut path = Vec::new();
+    let mut work = vec![(term, 0, None)];
+    while let Some((term, parent_length, dir)) = work.pop() {
+        path.truncate(parent_length);
+        path.extend(dir);
+        match term {
+            Term::Variable(_) => {},
+            Term::Abstraction(body) => {
+                work.push((body, path.len(), Some(Dir::Body)));
+            },
+            Term::Application(box Term::Abstraction(_), _) => {
+                return Some(path);
+            },
+            Term::Application(t1, t2) => {
+                work.push((t2, path.len(), Some(Dir::Arg)));
+                work.push((t1, path.len(), Some(Dir::Fun)));
+            },
+        }
+    }
+    None
+}
+
+/// Returns the subterm at the end of `path`, calling `body` on the body of each abstraction on the
+/// way, outermost first, before going into it
+fn follow<'t>(mut term: &'t mut Term, path: &[Dir], mut body: impl FnMut(&mut Term)) -> &'t mut Term {
+    for dir in path {
+        term = match (dir, term) {
+            (Dir::Fun, Term::Application(t1, _)) => &mut **t1,
+            (Dir::Arg, Term::Application(_, t2)) => &mut **t2,
+            (Dir::Body, Term::Abstraction(b)) => {
+                body(b);
+                &mut **b
+            },
+            _ => unreachable!("the path leads to a subterm"),
+        };
+    }
+    term
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::{EmptyContext, Normal, WeakHeadNormal};
+    use crate::evaluation::testing::assert_agrees_with_small_step;
+    use crate::notation::Named;
+    use crate::parser::parse_one;
+    use super::*;
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        assert_eq!(
+            Term::from(&parse_one(expected).unwrap()),
+            LocallyNamelessNormal::new().evaluate(&mut EmptyContext{}, Term::from(&parse_one(expr).unwrap()))
+        )
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_abstraction() {
+        assert_evaluates_to(r"\x.z", r"\x.(\y.y) z");
+    }
+
+    #[test]
+    pub fn test_evaluates_without_capture() {
+        assert_evaluates_to(r"\a.x", r"(\x.\y.x) x");
+        assert_evaluates_to(r"\a.\b.b", r"(\x.\y.x) (\y.y)");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_divergent_argument() {
+        assert_evaluates_to(r"y", r"(\x.y) ((\x.x x) (\x.x x))");
+    }
+
+    #[test]
+    pub fn test_weak_head_normal_runs_on_locally_nameless_terms() {
+        assert_eq!(
+            Term::from(&parse_one(r"\x.x ((\y.y) z)").unwrap()),
+            WeakHeadNormal::new().evaluate(&mut EmptyContext{}, Term::from(&parse_one(r"(\f.\x.x (f z)) (\y.y)").unwrap()))
+        );
+    }
+
+    #[test]
+    pub fn test_agrees_with_normal() {
+        assert_agrees_with_small_step(Normal::new(), |t| {
+            Named::from(&LocallyNamelessNormal::new().evaluate(&mut EmptyContext{}, Term::from(t)))
+        });
+    }
+}
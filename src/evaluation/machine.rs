@@ -0,0 +1,89 @@
+//! Pieces shared by the abstract machines.
+//!
+//! The machines evaluate De Bruijn terms, pairing each term with an environment of closures for
+//! its bound variables instead of substituting into it. Results are read back into terms by
+//! substituting the environment once evaluation is complete.
+use std::{
+    rc::Rc,
+};
+
+use crate::notation::{DeBruijn, Named};
+use crate::notation::conversion::{remove_names, restore_names, sorted_free_variables};
+
+/// A term along with the environment its variables are bound in
+#[derive(Clone)]
+pub struct Closure<'t> {
+    pub term: &'t DeBruijn,
+    pub env: Environment<'t>,
+}
+
+impl<'t> Closure<'t> {
+    pub fn new(term: &'t DeBruijn, env: Environment<'t>) -> Closure<'t> {
+        Closure { term, env }
+    }
+
+    /// Returns the term this closure represents, with its environment substituted into it
+    pub fn read_back(&self) -> DeBruijn {
+        self.read_back_at(self.term, 0)
+    }
+
+    fn read_back_at(&self, term: &DeBruijn, depth: u32) -> DeBruijn {
+        match term {
+            DeBruijn::Variable(index) if *index < depth => {
+                term.clone()
+            },
+            DeBruijn::Variable(index) => {
+                match self.env.get(index - depth) {
+                    Some(closure) => closure.read_back().shift(depth),
+                    None => DeBruijn::Variable(index - self.env.len()),
+                }
+            },
+            DeBruijn::Abstraction(body) => {
+                DeBruijn::Abstraction(box self.read_back_at(body, depth + 1))
+            },
+            DeBruijn::Application(t1, t2) => {
+                DeBruijn::Application(box self.read_back_at(t1, depth), box self.read_back_at(t2, depth))
+            },
+        }
+    }
+}
+
+struct Frame<'t> {
+    closure: Closure<'t>,
+    next: Environment<'t>,
+}
+
+/// A persistent list of closures, where the closure for the variable with index 0 is first
+#[derive(Clone)]
+pub struct Environment<'t>(Option<Rc<Frame<'t>>>);
+
+impl<'t> Environment<'t> {
+    pub fn empty() -> Environment<'t> {
+        Environment(None)
+    }
+
+    pub fn bind(&self, closure: Closure<'t>) -> Environment<'t> {
+        Environment(Some(Rc::new(Frame { closure, next: self.clone() })))
+    }
+
+    fn frames(&self) -> impl Iterator<Item=&Frame<'t>> {
+        std::iter::successors(self.0.as_deref(), |f| f.next.0.as_deref())
+    }
+
+    pub fn get(&self, index: u32) -> Option<&Closure<'t>> {
+        self.frames().nth(index as usize).map(|frame| &frame.closure)
+    }
+
+    pub fn len(&self) -> u32 {
+        self.frames().count() as u32
+    }
+}
+
+/// Runs a machine on a named term, converting to and from De Bruijn notation
+pub fn run_named<F>(named: &Named, run: F) -> Named
+    where F: FnOnce(&DeBruijn) -> DeBruijn
+{
+    let free_variables = sorted_free_variables(named);
+    let term = remove_names(named, &free_variables).expect("every free variable is in the naming context");
+    restore_names(&run(&term), &free_variables).expect("machines don't introduce free variables")
+}
@@ -2,15 +2,49 @@
 //!
 //! An expression of the form `(\x.t1) t2` is reducible, and known as a redex.
 
+mod applicative;
+mod beta_eta;
+mod call_by_need;
 mod call_by_value;
+mod cek;
 mod full;
+mod head_normal;
+mod head_spine;
+mod hybrid_applicative;
+mod hybrid_normal;
+mod krivine;
 mod lazy;
+mod locally_nameless_normal;
+mod machine;
+mod natural;
+mod nbe;
 mod normal;
+mod shared_normal;
+mod stats;
 
+#[cfg(test)]
+mod profiles;
+#[cfg(test)]
+pub mod testing;
+
+pub use applicative::Applicative;
+pub use beta_eta::BetaEta;
+pub use call_by_need::{CallByNeed, Heap};
 pub use call_by_value::CallByValue;
+pub use cek::Cek;
 pub use full::Full;
-pub use lazy::Lazy;
+pub use head_normal::HeadNormal;
+pub use head_spine::HeadSpine;
+pub use hybrid_applicative::HybridApplicative;
+pub use hybrid_normal::HybridNormal;
+pub use krivine::Krivine;
+pub use lazy::{Lazy, WeakHeadNormal};
+pub use locally_nameless_normal::LocallyNamelessNormal;
+pub use natural::Natural;
+pub use nbe::Nbe;
 pub use normal::Normal;
+pub use shared_normal::SharedNormal;
+pub use stats::{evaluate_with_stats, Stats};
 
 pub struct EmptyContext;
 
@@ -24,11 +58,38 @@ pub trait Evaluable {
     /// Perform one small step evaluation on the given term
     fn step(&self, ctx: &mut Self::Context, term: Self::Term) -> Option<Self::Term>;
 
-    /// Evaluate the given term as much as possible
-    fn evaluate(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Self::Term {
-        while let Some(new_term) = self.step(ctx, term.clone()) {
-            term = new_term;
+    /// Perform one small step evaluation on the given term in place, returning false and leaving
+    /// the term as it is if there's no step to take.
+    ///
+    /// By default this steps a copy of the term, since `step` gives nothing back when there's no
+    /// step to take. Strategies override it to change only the part of the term they reduce.
+    fn step_in_place(&self, ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        match self.step(ctx, term.clone()) {
+            Some(new_term) => {
+                *term = new_term;
+                true
+            },
+            None => false,
         }
+    }
+
+    /// Convert the final term of an evaluation back into a standalone term, for strategies that
+    /// leave references into their context in the terms they step
+    fn read_back(&self, _ctx: &Self::Context, term: Self::Term) -> Self::Term {
         term
     }
+
+    /// Evaluate the given term as much as possible
+    fn evaluate(&self, ctx: &mut Self::Context, term: Self::Term) -> Self::Term {
+        self.evaluate_counting_steps(ctx, term).0
+    }
+
+    /// Evaluate the given term as much as possible, also returning the number of steps taken
+    fn evaluate_counting_steps(&self, ctx: &mut Self::Context, mut term: Self::Term) -> (Self::Term, usize) {
+        let mut steps = 0;
+        while self.step_in_place(ctx, &mut term) {
+            steps += 1;
+        }
+        (self.read_back(ctx, term), steps)
+    }
 }
@@ -0,0 +1,224 @@
+//! Big-step (natural semantics) evaluation
+//!
+//! Rather than repeatedly rewriting the whole term, these evaluators compute the value of a term
+//! directly, using an environment that maps variables to values (call by value) or to unevaluated
+//! arguments and their environments (call by name). Abstractions evaluate to closures, which are
+//! read back into terms once evaluation is complete.
+//!
+//! Evaluation gets stuck in the same places as the small-step `CallByValue` and `Lazy` strategies,
+//! so both give the same results on terminating programs.
+use std::{
+    collections::HashSet,
+    rc::Rc,
+};
+
+use crate::notation::named::Term;
+use crate::substitution::Substitutable;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Order {
+    CallByValue,
+    CallByName,
+}
+
+pub struct Natural {
+    order: Order,
+}
+
+impl Natural {
+    /// Big-step equivalent of the `CallByValue` strategy
+    pub fn call_by_value() -> Natural {
+        Natural { order: Order::CallByValue }
+    }
+
+    /// Big-step equivalent of the `Lazy` strategy
+    pub fn call_by_name() -> Natural {
+        Natural { order: Order::CallByName }
+    }
+
+    /// Evaluate the given term as much as possible
+    pub fn evaluate(&self, term: &Term) -> Term {
+        self.eval(term, &Environment::empty()).read_back()
+    }
+
+    fn eval<'t>(&self, term: &'t Term, env: &Environment<'t>) -> Value<'t> {
+        match term {
+            Term::Variable(name) => {
+                match env.lookup(name) {
+                    Some(Binding::Value(value)) => value.clone(),
+                    Some(Binding::Thunk(t, thunk_env)) => self.eval(t, thunk_env),
+                    None => Value::Stuck(term.clone()),
+                }
+            },
+            Term::Abstraction(name, body) => {
+                Value::Closure(name, body, env.clone())
+            },
+            Term::Application(t1, t2) => {
+                match self.eval(t1, env) {
+                    Value::Closure(name, body, closure_env) => {
+                        let binding = match self.order {
+                            Order::CallByName => Binding::Thunk(t2, env.clone()),
+                            Order::CallByValue => match self.eval(t2, env) {
+                                value @ Value::Closure(_, _, _) => Binding::Value(value),
+                                value => {
+                                    let function = Value::Closure(name, body, closure_env);
+                                    return Value::Stuck(Term::Application(box function.read_back(), box value.read_back()));
+                                },
+                            },
+                        };
+
+                        self.eval(body, &closure_env.bind(name, binding))
+                    },
+                    Value::Stuck(t1) => {
+                        Value::Stuck(Term::Application(box t1, box env.close(t2)))
+                    },
+                }
+            },
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Value<'t> {
+    /// An abstraction, along with the environment it was evaluated in
+    Closure(&'t str, &'t Term, Environment<'t>),
+
+    /// A term that can't be evaluated any further, because it is headed by a free variable
+    Stuck(Term),
+}
+
+impl Value<'_> {
+    fn read_back(&self) -> Term {
+        match self {
+            Value::Closure(name, body, env) => {
+                env.close(&Term::Abstraction(name.to_string(), box (*body).clone()))
+            },
+            Value::Stuck(term) => {
+                term.clone()
+            },
+        }
+    }
+}
+
+enum Binding<'t> {
+    Value(Value<'t>),
+    Thunk(&'t Term, Environment<'t>),
+}
+
+impl Binding<'_> {
+    fn read_back(&self) -> Term {
+        match self {
+            Binding::Value(value) => value.read_back(),
+            Binding::Thunk(term, env) => env.close(term),
+        }
+    }
+}
+
+struct Frame<'t> {
+    name: &'t str,
+    binding: Binding<'t>,
+    next: Environment<'t>,
+}
+
+/// A persistent linked list of bindings, shared between closures
+#[derive(Clone)]
+struct Environment<'t>(Option<Rc<Frame<'t>>>);
+
+impl<'t> Environment<'t> {
+    fn empty() -> Environment<'t> {
+        Environment(None)
+    }
+
+    fn bind(&self, name: &'t str, binding: Binding<'t>) -> Environment<'t> {
+        Environment(Some(Rc::new(Frame { name, binding, next: self.clone() })))
+    }
+
+    fn frames(&self) -> impl Iterator<Item=&Frame<'t>> {
+        std::iter::successors(self.0.as_deref(), |f| f.next.0.as_deref())
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding<'t>> {
+        self.frames()
+            .find(|frame| frame.name == name)
+            .map(|frame| &frame.binding)
+    }
+
+    /// Substitutes the bindings of this environment into the free variables of `term`
+    fn close(&self, term: &Term) -> Term {
+        let mut seen = HashSet::new();
+        self.frames()
+            .filter(|frame| seen.insert(frame.name))
+            .fold(term.clone(), |term, frame| term.substitute(frame.name, &frame.binding.read_back()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::{CallByValue, Lazy};
+    use crate::evaluation::testing::assert_agrees_with_small_step;
+    use crate::parser::parse_one;
+    use super::*;
+
+    fn assert_evaluates_to(evaluator: Natural, expected: &str, expr: &str) {
+        let result = evaluator.evaluate(&parse_one(expr).unwrap());
+        assert!(
+            parse_one(expected).unwrap().is_alpha_equivalent(&result),
+            "expected {}, but found {}", expected, result
+        );
+    }
+
+    #[test]
+    pub fn test_call_by_value_does_not_evaluate_inside_abstraction() {
+        assert_evaluates_to(Natural::call_by_value(), r"\x.(\y.y) z", r"\x.(\y.y) z");
+    }
+
+    #[test]
+    pub fn test_call_by_value_does_not_evaluate_application_without_a_value_argument() {
+        assert_evaluates_to(Natural::call_by_value(), r"(\y.y) x", r"(\y.y) x");
+    }
+
+    #[test]
+    pub fn test_call_by_value_evaluates_three_argument_application() {
+        assert_evaluates_to(Natural::call_by_value(), r"\z.z", r"(\x.\y.\z. x y z) (\x.x) (\y.y) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_call_by_value_evaluates_with_and_tru_fls() {
+        assert_evaluates_to(Natural::call_by_value(), r"\t.\f.f", r"(\a.\b.(a b) (\t.\f.f)) (\t.\f.t) (\t.\f.f)");
+    }
+
+    #[test]
+    pub fn test_call_by_value_reads_back_closure_environment() {
+        assert_evaluates_to(Natural::call_by_value(), r"\y.\a.a", r"(\x.\y.x) (\a.a)");
+    }
+
+    #[test]
+    pub fn test_call_by_name_does_not_evaluate_argument() {
+        assert_evaluates_to(Natural::call_by_name(), r"\y.(\a.a) \b.b", r"(\x.\y.x) ((\a.a) \b.b)");
+    }
+
+    #[test]
+    pub fn test_call_by_name_discards_divergent_argument() {
+        assert_evaluates_to(Natural::call_by_name(), r"\z.z", r"(\x.\z.z) ((\x.x x) (\x.x x))");
+    }
+
+    #[test]
+    pub fn test_call_by_name_does_not_evaluate_application_with_variable_on_left() {
+        assert_evaluates_to(Natural::call_by_name(), r"z (\x.x) (\z.z)", r"z (\x.x) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_call_by_name_evaluates_application_fully() {
+        assert_evaluates_to(Natural::call_by_name(), r"z \z.z", r"((\x.x) z) \z.z");
+    }
+
+    #[test]
+    pub fn test_call_by_value_agrees_with_small_step() {
+        assert_agrees_with_small_step(CallByValue::new(), |t| Natural::call_by_value().evaluate(t));
+    }
+
+    #[test]
+    pub fn test_call_by_name_agrees_with_small_step() {
+        assert_agrees_with_small_step(Lazy::new(), |t| Natural::call_by_name().evaluate(t));
+    }
+}
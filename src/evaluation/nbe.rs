@@ -0,0 +1,213 @@
+//! Normalization by evaluation
+//!
+//! Computes the same normal forms as the `Normal` strategy, but instead of rewriting the term one
+//! redex at a time it evaluates De Bruijn terms into a semantic domain of closures and neutral
+//! terms (free variables applied to arguments), then reads the values back into terms. Reading
+//! back a closure applies it to a fresh variable, which is how evaluation reaches under binders.
+//!
+//! Arguments are evaluated lazily and at most once, so terms that only have a normal form because
+//! a divergent argument is discarded still normalize.
+use std::{
+    cell::RefCell,
+    rc::Rc,
+};
+
+use crate::notation::{DeBruijn, Named};
+use crate::vars::Variables;
+
+use super::machine;
+
+#[derive(Default)]
+pub struct Nbe;
+
+#[derive(Clone)]
+enum Value<'t> {
+    /// The body of an abstraction, along with the environment it was evaluated in
+    Closure(&'t DeBruijn, Environment<'t>),
+    Neutral(Rc<Neutral<'t>>),
+}
+
+enum Neutral<'t> {
+    /// A variable, identified by its De Bruijn level (the number of binders above its own)
+    Variable(u32),
+    Application(Rc<Neutral<'t>>, Rc<Thunk<'t>>),
+}
+
+enum ThunkState<'t> {
+    Delayed(&'t DeBruijn, Environment<'t>),
+    Forced(Value<'t>),
+}
+
+/// A lazily evaluated value, which is only evaluated once
+struct Thunk<'t>(RefCell<ThunkState<'t>>);
+
+impl<'t> Thunk<'t> {
+    fn delayed(term: &'t DeBruijn, env: Environment<'t>) -> Rc<Thunk<'t>> {
+        Rc::new(Thunk(RefCell::new(ThunkState::Delayed(term, env))))
+    }
+
+    fn variable(level: u32) -> Rc<Thunk<'t>> {
+        Rc::new(Thunk(RefCell::new(ThunkState::Forced(Value::Neutral(Rc::new(Neutral::Variable(level)))))))
+    }
+
+    fn force(&self) -> Value<'t> {
+        let (term, env) = match &*self.0.borrow() {
+            ThunkState::Forced(value) => return value.clone(),
+            ThunkState::Delayed(term, env) => (*term, env.clone()),
+        };
+
+        let value = eval(term, &env);
+        *self.0.borrow_mut() = ThunkState::Forced(value.clone());
+        value
+    }
+}
+
+struct Frame<'t> {
+    thunk: Rc<Thunk<'t>>,
+    next: Environment<'t>,
+}
+
+/// A persistent list of thunks, where the thunk for the variable with index 0 is first
+#[derive(Clone)]
+struct Environment<'t>(Option<Rc<Frame<'t>>>);
+
+impl<'t> Environment<'t> {
+    fn empty() -> Environment<'t> {
+        Environment(None)
+    }
+
+    fn bind(&self, thunk: Rc<Thunk<'t>>) -> Environment<'t> {
+        Environment(Some(Rc::new(Frame { thunk, next: self.clone() })))
+    }
+
+    fn get(&self, index: u32) -> Option<&Rc<Thunk<'t>>> {
+        std::iter::successors(self.0.as_deref(), |f| f.next.0.as_deref())
+            .nth(index as usize)
+            .map(|frame| &frame.thunk)
+    }
+}
+
+fn eval<'t>(term: &'t DeBruijn, env: &Environment<'t>) -> Value<'t> {
+    match term {
+        DeBruijn::Variable(index) => {
+            env.get(*index)
+                .expect("free variables are bound in the initial environment")
+                .force()
+        },
+        DeBruijn::Abstraction(body) => {
+            Value::Closure(body, env.clone())
+        },
+        DeBruijn::Application(t1, t2) => {
+            let arg = Thunk::delayed(t2, env.clone());
+            match eval(t1, env) {
+                Value::Closure(body, closure_env) => eval(body, &closure_env.bind(arg)),
+                Value::Neutral(neutral) => Value::Neutral(Rc::new(Neutral::Application(neutral, arg))),
+            }
+        },
+    }
+}
+
+/// Reads a value back into a term, where `depth` is the number of binders surrounding it
+fn quote(value: &Value<'_>, depth: u32) -> DeBruijn {
+    match value {
+        Value::Closure(body, env) => {
+            let body_value = eval(body, &env.bind(Thunk::variable(depth)));
+            DeBruijn::Abstraction(box quote(&body_value, depth + 1))
+        },
+        Value::Neutral(neutral) => {
+            quote_neutral(neutral, depth)
+        },
+    }
+}
+
+fn quote_neutral(neutral: &Neutral<'_>, depth: u32) -> DeBruijn {
+    match neutral {
+        Neutral::Variable(level) => {
+            DeBruijn::Variable(depth - 1 - level)
+        },
+        Neutral::Application(function, arg) => {
+            DeBruijn::Application(box quote_neutral(function, depth), box quote(&arg.force(), depth))
+        },
+    }
+}
+
+impl Nbe {
+    pub fn new() -> Nbe {
+        Nbe {}
+    }
+
+    /// Returns the normal form of the given term
+    ///
+    /// This will not terminate if the term has no normal form.
+    pub fn normalize(&self, term: &Named) -> Named {
+        let free_variables = term.free_variables().len() as u32;
+        machine::run_named(term, |t| self.normalize_de_bruijn(t, free_variables))
+    }
+
+    /// Returns the normal form of the given De Bruijn term, which has `free_variables` free
+    /// variables
+    pub fn normalize_de_bruijn(&self, term: &DeBruijn, free_variables: u32) -> DeBruijn {
+        // Free variables are neutral, with the variable at index 0 being the innermost
+        let env = (0..free_variables).fold(Environment::empty(), |env, level| env.bind(Thunk::variable(level)));
+        quote(&eval(term, &env), free_variables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::Normal;
+    use crate::evaluation::testing::assert_agrees_with_small_step;
+    use crate::parser::parse_one;
+    use super::*;
+
+    fn assert_normalizes_to(expected: &str, expr: &str) {
+        let result = Nbe::new().normalize(&parse_one(expr).unwrap());
+        assert!(
+            parse_one(expected).unwrap().is_alpha_equivalent(&result),
+            "expected {}, but found {}", expected, result
+        );
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_variable() {
+        assert_normalizes_to("x", "x");
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_abstraction() {
+        assert_normalizes_to(r"\x.z x", r"\x.((\y.y) z) x");
+    }
+
+    #[test]
+    pub fn test_evaluates_application_with_variable_on_left() {
+        assert_normalizes_to(r"z (\x.x) (\z.z)", r"z ((\y.y) \x.x) (\z.z)");
+    }
+
+    #[test]
+    pub fn test_evaluates_under_abstraction_in_function_position() {
+        assert_normalizes_to(r"x (\a.a) c", r"x (\a.(\b.b) a) c");
+    }
+
+    #[test]
+    pub fn test_discards_divergent_argument() {
+        assert_normalizes_to(r"y", r"(\x.y) ((\x.x x) (\x.x x))");
+    }
+
+    #[test]
+    pub fn test_avoids_capturing_free_variables() {
+        assert_normalizes_to(r"\b.a b", r"(\x.\a.x a) a");
+    }
+
+    #[test]
+    pub fn test_church_numeral_addition() {
+        assert_normalizes_to(
+            r"\s.\z.s (s (s z))",
+            r"(\m.\n.\s.\z. m s (n s z)) (\s.\z.s (s z)) (\s.\z.s z)"
+        );
+    }
+
+    #[test]
+    pub fn test_agrees_with_normal() {
+        assert_agrees_with_small_step(Normal::new(), |t| Nbe::new().normalize(t));
+    }
+}
@@ -2,10 +2,11 @@
 //!
 //! This strategy will evaluate the left and outermost terms first.
 use crate::notation::named::Term;
-use crate::substitution::Substitutable;
+use crate::path::{contract_in_place, leftmost_outermost_redex, next_leftmost_outermost_redex};
 
 use super::Evaluable;
 
+#[derive(Default)]
 pub struct Normal;
 
 impl Normal {
@@ -18,28 +19,34 @@ impl Evaluable for Normal {
     type Term = Term;
     type Context = super::EmptyContext;
 
-    fn step(&self, ctx: &mut Self::Context, term: Self::Term) -> Option<Self::Term> {
-        match term {
-            Term::Abstraction(arg, box body) => {
-                self.step(ctx, body)
-                    .map(|body_evaluated| Term::Abstraction(arg, box body_evaluated))
-            },
-            Term::Application(box Term::Abstraction(name, body), box arg) => {
-                Some(body.substitute(name.as_str(), &arg))
-            }
-            Term::Application(box t1, t2) if t1.is_redex() => {
-                self.step(ctx, t1)
-                    .map(|t1_evaluated| Term::Application(box t1_evaluated, t2))
-            },
-            Term::Application(t1, box t2) => {
-                self.step(ctx, t2)
-                    .map(|t2_evaluated| Term::Application(t1, box t2_evaluated))
-            },
-            _ => {
-                None
-            },
+    fn step(&self, ctx: &mut Self::Context, mut term: Self::Term) -> Option<Self::Term> {
+        if self.step_in_place(ctx, &mut term) {
+            Some(term)
+        } else {
+            None
         }
     }
+
+    fn step_in_place(&self, _ctx: &mut Self::Context, term: &mut Self::Term) -> bool {
+        // Finding the redex and contracting it in place, rather than recursing down to it, keeps
+        // deep terms from overflowing the stack
+        match leftmost_outermost_redex(term) {
+            Some(path) => contract_in_place(term, &path),
+            None => false,
+        }
+    }
+
+    fn evaluate_counting_steps(&self, _ctx: &mut Self::Context, mut term: Self::Term) -> (Self::Term, usize) {
+        // Each search for the next redex resumes from the one just contracted
+        let mut steps = 0;
+        let mut next = leftmost_outermost_redex(&term);
+        while let Some(path) = next {
+            contract_in_place(&mut term, &path);
+            steps += 1;
+            next = next_leftmost_outermost_redex(&term, &path);
+        }
+        (term, steps)
+    }
 }
 
 #[cfg(test)]
@@ -99,4 +106,9 @@ mod tests {
     pub fn test_evaluates_application_fully2() {
         assert_evaluates_to(r"z \z.z", r"((\x.x) z) \z.z");
     }
+
+    #[test]
+    pub fn test_evaluates_under_abstraction_in_function_position() {
+        assert_evaluates_to(r"x (\a.a) c", r"x (\a.(\b.b) a) c");
+    }
 }
@@ -0,0 +1,146 @@
+//! Profiles of the small-step strategies, on both notations, over the workloads in `WORKLOADS`.
+//!
+//! `test_profiles_workloads` checks the steps each takes, the largest term it reaches along the
+//! way and the substitutions it does against `PROFILES`, so changes to substitution that make
+//! evaluation do more work show up as a failing test. Changes that only make each substitution
+//! slower don't; for those, run `cargo bench` (see `benches/evaluation.rs`), or
+//! `cargo test --release print_profiles -- --ignored --nocapture` to see the time each took next
+//! to its profile.
+use std::cmp;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::notation::{locally_nameless, DeBruijn, Notation};
+use crate::notation::named::Term;
+use crate::notation::shared::Store;
+use crate::parser::parse;
+use crate::substitution;
+
+use super::{CallByValue, EmptyContext, Evaluable, Full, Lazy, LocallyNamelessNormal, Normal, SharedNormal};
+
+/// Each workload, written on top of the definitions in `examples/workloads.lambda`, computes a
+/// numeral and applies it to the identity twice, so that even the weak strategies have to do all
+/// of the work to reach `\y.y`. Sorting weighs each element of the sorted list by its position, so
+/// the result depends on the order.
+const WORKLOADS: &[(&str, &str)] = &[
+    ("plus", "plus c5 c5"),
+    ("times", "times c4 c5"),
+    ("exp", "exp c2 c4"),
+    ("factorial", "fact c3"),
+    ("ackermann", "ack c2 c2"),
+    ("sort", "weigh (sort (cons c3 (cons c1 (cons c2 nil))))"),
+];
+
+/// Strategies that take more steps than this, or build a bigger term, are taken not to finish
+const STEP_LIMIT: usize = 20_000;
+const SIZE_LIMIT: usize = 20_000;
+
+fn workload(name: &str) -> Term {
+    let (_, expression) = WORKLOADS.iter().find(|(n, _)| *n == name).expect("a known workload");
+    parse(&format!(r"{}{} (\x.x) (\y.y);", include_str!("../../examples/workloads.lambda"), expression)).unwrap().pop().unwrap()
+}
+
+/// The steps taken to evaluate a workload, the size of the largest term along the way and the
+/// number of variables named substitution replaced, or `None` if the strategy didn't finish
+type Profile = Option<(usize, usize, usize)>;
+
+/// Evaluates the term, returning its profile and the time spent stepping it
+fn profile<E: Evaluable>(strategy: &E, ctx: &mut E::Context, mut term: E::Term, size: impl Fn(&E::Term) -> usize) -> (Profile, Duration) {
+    let counts = substitution::counts();
+    let mut time = Duration::default();
+    let mut peak_size = size(&term);
+    for steps in 0..STEP_LIMIT {
+        let start = Instant::now();
+        let stepped = strategy.step_in_place(ctx, &mut term);
+        time += start.elapsed();
+        if !stepped {
+            let substitutions = substitution::counts().substitutions - counts.substitutions;
+            return (Some((steps, peak_size, substitutions)), time);
+        }
+        peak_size = cmp::max(peak_size, size(&term));
+        if peak_size > SIZE_LIMIT {
+            return (None, time);
+        }
+    }
+    (None, time)
+}
+
+fn profile_named<E: Evaluable<Term=Term, Context=EmptyContext>>(strategy: &E, term: Term) -> (Profile, Duration) {
+    profile(strategy, &mut EmptyContext{}, term, Term::size)
+}
+
+fn profile_locally_nameless(term: Term) -> (Profile, Duration) {
+    profile(&LocallyNamelessNormal::new(), &mut EmptyContext{}, locally_nameless::Term::from(&term), locally_nameless::Term::size)
+}
+
+fn profile_shared(term: Term) -> (Profile, Duration) {
+    let mut store = Store::new();
+    let term = store.import(&DeBruijn::from(&term));
+    profile(&SharedNormal::new(), &mut store, term, |term| term.size())
+}
+
+/// The strategies in the suite, in the order of the columns of `PROFILES`
+const STRATEGIES: &[&str] = &["normal", "call_by_value", "lazy", "full", "locally_nameless", "shared"];
+
+fn profile_strategy(strategy: &str, term: Term) -> (Profile, Duration) {
+    match strategy {
+        "normal" => profile_named(&Normal::new(), term),
+        "call_by_value" => profile_named(&CallByValue::new(), term),
+        "lazy" => profile_named(&Lazy::new(), term),
+        "full" => profile_named(&Full::new(), term),
+        "locally_nameless" => profile_locally_nameless(term),
+        "shared" => profile_shared(term),
+        _ => unreachable!("unknown strategy {}", strategy),
+    }
+}
+
+/// Profiles every strategy on every workload, returning the profiles and a table of them that
+/// includes the time each took if `times` is set
+fn profile_workloads(times: bool) -> (Vec<Vec<Profile>>, String) {
+    let mut table = format!("{:<12}", "");
+    for strategy in STRATEGIES {
+        write!(table, "{:>30}", strategy).unwrap();
+    }
+    let mut profiles = Vec::new();
+    for (name, _) in WORKLOADS {
+        write!(table, "\n{:<12}", name).unwrap();
+        let mut row = Vec::new();
+        for strategy in STRATEGIES {
+            let (profile, time) = profile_strategy(strategy, workload(name));
+            let cell = match profile {
+                Some((steps, peak_size, substitutions)) if times => format!("{} / {} / {} / {:.2?}", steps, peak_size, substitutions, time),
+                Some((steps, peak_size, substitutions)) => format!("{} / {} / {}", steps, peak_size, substitutions),
+                None => "-".to_string(),
+            };
+            write!(table, "{:>30}", cell).unwrap();
+            row.push(profile);
+        }
+        profiles.push(row);
+    }
+    (profiles, table)
+}
+
+#[test]
+fn test_profiles_workloads() {
+    let (profiles, table) = profile_workloads(false);
+    assert_eq!(PROFILES, &profiles[..], "\nsteps / peak term size / substitutions\n{}", table);
+}
+
+#[test]
+#[ignore]
+fn print_profiles() {
+    let (_, table) = profile_workloads(true);
+    println!("steps / peak term size / substitutions / time\n{}", table);
+}
+
+/// The expected profiles, one row per workload. Call by value and full reduction evaluate the
+/// argument of `Y` before unfolding it, so never finish a workload that recurses through it. The
+/// locally nameless and shared strategies have their own substitution, so count none.
+const PROFILES: &[&[Profile]] = &[
+    &[Some((18, 47, 27)), Some((18, 47, 27)), Some((18, 47, 27)), Some((18, 47, 27)), Some((18, 47, 0)), Some((18, 47, 0))],
+    &[Some((33, 73, 52)), Some((30, 77, 37)), Some((33, 73, 52)), Some((30, 77, 37)), Some((33, 73, 0)), Some((33, 73, 0))],
+    &[Some((50, 73, 68)), Some((39, 95, 46)), Some((50, 73, 68)), Some((39, 95, 46)), Some((50, 73, 0)), Some((50, 73, 0))],
+    &[Some((654, 1436, 696)), None, Some((654, 1436, 696)), None, Some((654, 1436, 0)), Some((654, 1436, 0))],
+    &[Some((64, 98, 82)), Some((62, 107, 78)), Some((64, 98, 82)), Some((62, 107, 78)), Some((64, 98, 0)), Some((64, 98, 0))],
+    &[Some((464, 2448, 535)), None, Some((464, 2448, 535)), None, Some((464, 2448, 0)), Some((464, 2448, 0))],
+];
@@ -0,0 +1,108 @@
+//! Normal order reduction on hash-consed terms
+//!
+//! This contracts the same redexes as the `Normal` strategy, on terms interned in a `Store`.
+//! Stepping never copies a term, only builds the nodes on the path to the contracted redex, and
+//! subterms already in normal form are skipped using the flag cached in each node.
+use crate::notation::shared::{Node, Store, Term};
+
+use super::Evaluable;
+
+#[derive(Default)]
+pub struct SharedNormal;
+
+impl SharedNormal {
+    pub fn new() -> SharedNormal {
+        SharedNormal {}
+    }
+}
+
+impl Evaluable for SharedNormal {
+    type Term = Term;
+    type Context = Store;
+
+    fn step(&self, store: &mut Self::Context, term: Self::Term) -> Option<Self::Term> {
+        if term.is_normal_form() {
+            return None;
+        }
+
+        match term.node() {
+            Node::Abstraction(body) => {
+                self.step(store, body.clone())
+                    .map(|body_evaluated| store.abstraction(body_evaluated))
+            },
+            Node::Application(t1, t2) => {
+                match t1.node() {
+                    Node::Abstraction(body) => {
+                        Some(store.instantiate(body, t2))
+                    },
+                    _ if !t1.is_normal_form() => {
+                        let t1_evaluated = self.step(store, t1.clone())?;
+                        Some(store.application(t1_evaluated, t2.clone()))
+                    },
+                    _ => {
+                        let t2_evaluated = self.step(store, t2.clone())?;
+                        Some(store.application(t1.clone(), t2_evaluated))
+                    },
+                }
+            },
+            Node::Variable(_) => {
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::Normal;
+    use crate::evaluation::testing::assert_agrees_with_small_step;
+    use crate::notation::{DeBruijn, Named};
+    use crate::notation::conversion::{remove_names, restore_names, sorted_free_variables};
+    use crate::parser::parse_one;
+    use super::*;
+
+    fn evaluate(named: &Named) -> Named {
+        let free_variables = sorted_free_variables(named);
+        let mut store = Store::new();
+        let term = store.import(&remove_names(named, &free_variables).unwrap());
+        let result = SharedNormal::new().evaluate(&mut store, term);
+        restore_names(&DeBruijn::from(&result), &free_variables).unwrap()
+    }
+
+    fn assert_evaluates_to(expected: &str, expr: &str) {
+        assert!(parse_one(expected).unwrap().is_alpha_equivalent(&evaluate(&parse_one(expr).unwrap())));
+    }
+
+    #[test]
+    pub fn test_evaluates_inside_abstraction() {
+        assert_evaluates_to(r"\x.z", r"\x.(\y.y) z");
+    }
+
+    #[test]
+    pub fn test_evaluates_without_capture() {
+        assert_evaluates_to(r"\a.x", r"(\x.\y.x) x");
+    }
+
+    #[test]
+    pub fn test_does_not_evaluate_divergent_argument() {
+        assert_evaluates_to(r"y", r"(\x.y) ((\x.x x) (\x.x x))");
+    }
+
+    #[test]
+    pub fn test_multiplies_large_church_numerals() {
+        let numeral = |n: usize| format!(r"(\s.\z.{}z{})", "s (".repeat(n), ")".repeat(n));
+        let named = parse_one(&format!(r"(\m.\n.\s.\z.m (n s) z) {} {}", numeral(10), numeral(10))).unwrap();
+        let mut store = Store::new();
+        let term = store.import(&DeBruijn::from(&named));
+        let result = SharedNormal::new().evaluate(&mut store, term);
+        let expected = (0..100).fold(DeBruijn::Variable(0), |z, _| DeBruijn::Application(box DeBruijn::Variable(1), box z));
+        assert_eq!(DeBruijn::Abstraction(box DeBruijn::Abstraction(box expected)), DeBruijn::from(&result));
+        // Only the nodes of the result are still in use
+        assert!(store.len() < 200, "store has {} nodes", store.len());
+    }
+
+    #[test]
+    pub fn test_agrees_with_normal() {
+        assert_agrees_with_small_step(Normal::new(), evaluate);
+    }
+}
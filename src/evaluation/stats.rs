@@ -0,0 +1,208 @@
+//! Statistics about an evaluation
+//!
+//! These say where the time in an evaluation goes: how many steps it took, how much substituting
+//! and renaming those steps did, how large the term grew, and which macros were applied most. A
+//! redex is counted against a macro when its function is the body of that macro as it was
+//! defined, so applying `plus` counts against `plus`, but the partial applications it reduces to
+//! don't.
+use std::cmp;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::notation::Notation;
+use crate::notation::named::Term;
+use crate::path::{contracted_redex, redex_paths, subterm_at, Path};
+use crate::substitution;
+
+use super::Evaluable;
+
+/// Counts of the work done to evaluate a term
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Steps taken by the strategy
+    pub steps: usize,
+
+    /// Free occurrences of a variable replaced by substitution, including by renaming
+    pub substitutions: usize,
+
+    /// Bound variables renamed to avoid capture
+    pub renamings: usize,
+
+    /// Number of nodes in the largest term along the way
+    pub max_size: usize,
+
+    /// Depth of the deepest term along the way
+    pub max_depth: usize,
+
+    /// The number of redexes contracted whose function came from each macro
+    pub macro_contractions: HashMap<String, usize>,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+
+    /// Returns the macros redexes were contracted for, most often contracted first
+    pub fn most_contracted_macros(&self) -> Vec<(&str, usize)> {
+        let mut result: Vec<(&str, usize)> = self.macro_contractions
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+            .collect();
+        result.sort_by(|(name1, count1), (name2, count2)| count2.cmp(count1).then(name1.cmp(name2)));
+        result
+    }
+
+    fn observe(&mut self, term: &Term) {
+        self.max_size = cmp::max(self.max_size, term.size());
+        self.max_depth = cmp::max(self.max_depth, term.depth());
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "steps: {}, substitutions: {}, renamings: {}", self.steps, self.substitutions, self.renamings)?;
+        write!(f, "max size: {}, max depth: {}", self.max_size, self.max_depth)?;
+        for (name, count) in self.most_contracted_macros() {
+            write!(f, "\n{}: {} contracted", name, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates a term as much as possible with the given strategy, as `Evaluable::evaluate` does,
+/// also returning statistics about the evaluation. `macros` are the macros the term was parsed
+/// with, whose contractions are counted.
+pub fn evaluate_with_stats<E>(strategy: &E, ctx: &mut E::Context, mut term: Term, macros: &HashMap<&str, Term>) -> (Term, Stats)
+    where E: Evaluable<Term=Term>
+{
+    // Macros with the same body are counted against whichever has the first name
+    let mut macros: Vec<(&str, &Term)> = macros.iter().map(|(name, body)| (*name, body)).collect();
+    macros.sort_by_key(|(name, _)| *name);
+
+    let mut stats = Stats::new();
+    stats.observe(&term);
+    loop {
+        // Finding which redex a strategy contracted means comparing against a copy of the term,
+        // so only keep one when the contracted redex could have come from a macro
+        let candidates = macro_redexes(&term, &macros);
+        let before = if candidates.is_empty() { None } else { Some(term.clone()) };
+
+        // Finding the contracted redex substitutes too, so only the step itself is counted
+        let counts = substitution::counts();
+        let stepped = strategy.step_in_place(ctx, &mut term);
+        let after = substitution::counts();
+        stats.substitutions += after.substitutions - counts.substitutions;
+        stats.renamings += after.renamings - counts.renamings;
+        if !stepped {
+            break;
+        }
+        stats.steps += 1;
+        stats.observe(&term);
+
+        if let Some(path) = before.and_then(|before| contracted_redex(&before, &term)) {
+            if let Some((_, name)) = candidates.into_iter().find(|(candidate, _)| *candidate == path) {
+                *stats.macro_contractions.entry(name.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    (strategy.read_back(ctx, term), stats)
+}
+
+/// Returns the paths to the redexes whose function is the body of a macro, with that macro
+fn macro_redexes<'m>(term: &Term, macros: &[(&'m str, &Term)]) -> Vec<(Path, &'m str)> {
+    redex_paths(term)
+        .into_iter()
+        .filter_map(|path| {
+            let function = match subterm_at(term, &path) {
+                Some(Term::Application(function, _)) => function,
+                _ => return None,
+            };
+            macros.iter()
+                .find(|(_, body)| **function == **body)
+                .map(|(name, _)| (path, *name))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::{CallByValue, EmptyContext, Normal};
+    use crate::parser::{parse_one, Parser};
+    use super::*;
+
+    /// Evaluates the last term in `text`, counting the macros defined in it
+    fn stats_of<E: Evaluable<Term=Term, Context=EmptyContext>>(strategy: &E, text: &str) -> (Term, Stats) {
+        let mut parser = Parser::new();
+        let term = parser.parse(text).unwrap().last().unwrap();
+        evaluate_with_stats(strategy, &mut EmptyContext{}, term, parser.macros())
+    }
+
+    #[test]
+    pub fn test_counts_steps_and_substitutions() {
+        let (result, stats) = stats_of(&Normal::new(), r"(\x.x x) ((\y.y) z)");
+        assert_eq!(parse_one("z z").unwrap(), result);
+        assert_eq!(3, stats.steps);
+        // Two occurrences of x, then one of y in each copy of the argument
+        assert_eq!(4, stats.substitutions);
+        assert_eq!(0, stats.renamings);
+    }
+
+    #[test]
+    pub fn test_counts_renamings() {
+        let (_, stats) = stats_of(&Normal::new(), r"(\x.\y.x y) y");
+        assert_eq!(1, stats.steps);
+        assert_eq!(1, stats.renamings);
+    }
+
+    #[test]
+    pub fn test_counts_only_substitutions_done_by_steps_when_macros_are_contracted() {
+        let text = r"
+            id = \x.x;
+            k = \x.\y.x;
+            k (id y) z;
+        ";
+        let (result, stats) = stats_of(&Normal::new(), text);
+        assert_eq!(parse_one("y").unwrap(), result);
+        assert_eq!(vec![("id", 1), ("k", 1)], stats.most_contracted_macros());
+        // One occurrence of x for `k`, which renames the unused y in `\y.x` to avoid capturing
+        // `id y`, none for the renamed y, and one for `id`
+        assert_eq!(3, stats.steps);
+        assert_eq!(2, stats.substitutions);
+        assert_eq!(1, stats.renamings);
+    }
+
+    #[test]
+    pub fn test_tracks_largest_term() {
+        // The term grows to `(\x.x x) z ((\x.x x) z)` before shrinking to `z z (z z)`
+        let (_, stats) = stats_of(&Normal::new(), r"(\f.f (f z)) (\x.x x)");
+        assert_eq!(13, stats.max_size);
+        assert_eq!(4, stats.max_depth);
+    }
+
+    #[test]
+    pub fn test_counts_contracted_macros() {
+        let text = r"
+            id = \x.x;
+            twice = \f.\x.f (f x);
+            twice id (id z);
+        ";
+        let (_, stats) = stats_of(&Normal::new(), text);
+        assert_eq!(vec![("id", 3), ("twice", 1)], stats.most_contracted_macros());
+    }
+
+    #[test]
+    pub fn test_counts_macros_contracted_by_other_strategies() {
+        let text = r"
+            id = \x.x;
+            k = \x.\y.x;
+            k (id \a.a) (id \b.b);
+        ";
+        let (_, normal) = stats_of(&Normal::new(), text);
+        let (_, call_by_value) = stats_of(&CallByValue::new(), text);
+        assert_eq!(vec![("id", 1), ("k", 1)], normal.most_contracted_macros());
+        // Call by value also evaluates the argument that `k` discards
+        assert_eq!(vec![("id", 2), ("k", 1)], call_by_value.most_contracted_macros());
+    }
+}
@@ -0,0 +1,126 @@
+//! Helpers for checking that evaluators agree with the small-step strategies on random terms
+use crate::notation::Notation;
+use crate::notation::named::Term;
+
+use super::{EmptyContext, Evaluable};
+
+/// A small xorshift generator, so that the property tests are reproducible
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random(seed)
+    }
+
+    pub fn next(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+
+    /// Generates a closed term, reusing a small set of names to exercise shadowing and capture
+    pub fn term(&mut self, depth: usize, scope: &mut Vec<&'static str>) -> Term {
+        self.term_over(depth, scope, &["x", "y", "z"])
+    }
+
+    /// Generates a term whose variables are taken from `scope`, and whose abstractions bind names
+    /// from `names`
+    pub fn term_over(&mut self, depth: usize, scope: &mut Vec<&'static str>, names: &[&'static str]) -> Term {
+        match self.next(if depth == 0 { 2 } else { 5 }) {
+            0 if !scope.is_empty() => {
+                Term::Variable(scope[self.next(scope.len())].to_string())
+            },
+            0..=2 => {
+                let name = names[self.next(names.len())];
+                scope.push(name);
+                let body = self.term_over(depth.saturating_sub(1), scope, names);
+                scope.pop();
+                Term::Abstraction(name.to_string(), box body)
+            },
+            _ => {
+                let t1 = self.term_over(depth - 1, scope, names);
+                let t2 = self.term_over(depth - 1, scope, names);
+                Term::Application(box t1, box t2)
+            },
+        }
+    }
+}
+
+/// Evaluates with a small-step strategy, giving up on terms that take too long to terminate or
+/// grow too large
+pub fn small_step<E>(strategy: &E, mut term: Term) -> Option<Term>
+    where E: Evaluable<Term=Term, Context=EmptyContext>
+{
+    for _ in 0..100 {
+        match strategy.step(&mut EmptyContext{}, term.clone()) {
+            Some(new_term) if new_term.size() <= 500 => term = new_term,
+            Some(_) => return None,
+            None => return Some(term),
+        }
+    }
+    None
+}
+
+/// Asserts that `evaluate` gives the same result as `strategy`, up to alpha-equivalence, on
+/// random closed terms that terminate under `strategy`
+pub fn assert_agrees_with_small_step<E, F>(strategy: E, evaluate: F)
+    where E: Evaluable<Term=Term, Context=EmptyContext>,
+          F: Fn(&Term) -> Term
+{
+    let mut random = Random::new(0x5eed_1234_abcd_ef01);
+    let mut checked = 0;
+    for _ in 0..2000 {
+        let term = random.term(6, &mut Vec::new());
+        if let Some(expected) = small_step(&strategy, term.clone()) {
+            let result = evaluate(&term);
+            assert!(
+                expected.is_alpha_equivalent(&result),
+                "{} evaluated to {}, but small-step evaluation gave {}", term, result, expected
+            );
+            checked += 1;
+        }
+    }
+    assert!(checked > 1000);
+}
+
+/// Asserts that two small-step strategies give the same result, up to alpha-equivalence, on random
+/// closed terms that terminate under both strategies
+pub fn assert_agrees_when_both_terminate<E1, E2>(strategy1: E1, strategy2: E2)
+    where E1: Evaluable<Term=Term, Context=EmptyContext>,
+          E2: Evaluable<Term=Term, Context=EmptyContext>
+{
+    let mut random = Random::new(0x5eed_1234_abcd_ef01);
+    let mut checked = 0;
+    for _ in 0..2000 {
+        let term = random.term(6, &mut Vec::new());
+        if let (Some(result1), Some(result2)) = (small_step(&strategy1, term.clone()), small_step(&strategy2, term.clone())) {
+            assert!(
+                result1.is_alpha_equivalent(&result2),
+                "{} evaluated to {} and {}", term, result1, result2
+            );
+            checked += 1;
+        }
+    }
+    assert!(checked > 1000);
+}
+
+/// Asserts that `strategy` takes the same steps as `defined_step`, which follows the definition of
+/// the strategy directly, on random closed terms
+pub fn assert_steps_as_defined<E, F>(strategy: E, defined_step: F)
+    where E: Evaluable<Term=Term, Context=EmptyContext>,
+          F: Fn(&Term) -> Option<Term>
+{
+    let mut random = Random::new(0x5eed_1234_abcd_ef01);
+    for _ in 0..500 {
+        let mut term = random.term(6, &mut Vec::new());
+        for _ in 0..20 {
+            let stepped = strategy.step(&mut EmptyContext{}, term.clone());
+            assert_eq!(defined_step(&term), stepped, "stepping {}", term);
+            match stepped {
+                Some(new_term) if new_term.size() <= 500 => term = new_term,
+                _ => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Reduction graphs
+//!
+//! The reduction graph of a term has a node for every term reachable from it by contracting
+//! redexes, in any order, and an edge for every single contraction. Terms that differ only in the
+//! names of bound variables are the same node. Graphs of terms without a normal form can be
+//! infinite, so exploration stops adding nodes once a limit is reached.
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use crate::notation::named::Term;
+use crate::path::{contract_at, redex_paths, Dir, Path};
+
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+
+    /// The path to the redex contracted in `from` to give `to`
+    pub redex: Path,
+}
+
+pub struct ReductionGraph {
+    /// The terms in the graph, starting with the term that was explored
+    pub nodes: Vec<Term>,
+    pub edges: Vec<Edge>,
+
+    /// False if the node limit was reached before the whole graph was explored
+    pub complete: bool,
+}
+
+impl ReductionGraph {
+    /// Explores the reduction graph of the given term, stopping once it has `limit` nodes
+    pub fn explore(term: &Term, limit: usize) -> ReductionGraph {
+        let mut graph = ReductionGraph {
+            nodes: vec![term.clone()],
+            edges: Vec::new(),
+            complete: true,
+        };
+
+        let mut queue: VecDeque<usize> = vec![0].into_iter().collect();
+        while let Some(from) = queue.pop_front() {
+            for redex in redex_paths(&graph.nodes[from]) {
+                let reduct = contract_at(&graph.nodes[from], &redex).unwrap();
+                let to = match graph.find(&reduct) {
+                    Some(to) => to,
+                    None if graph.nodes.len() < limit => {
+                        graph.nodes.push(reduct);
+                        queue.push_back(graph.nodes.len() - 1);
+                        graph.nodes.len() - 1
+                    },
+                    None => {
+                        graph.complete = false;
+                        continue;
+                    },
+                };
+                graph.edges.push(Edge { from, to, redex });
+            }
+        }
+
+        graph
+    }
+
+    fn find(&self, term: &Term) -> Option<usize> {
+        self.nodes.iter().position(|node| node.is_alpha_equivalent(term))
+    }
+
+    /// Returns the nodes that have no redexes
+    pub fn normal_forms(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&node| self.nodes[node].is_normal_form())
+            .collect()
+    }
+
+    /// Renders the graph in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph reductions {\n");
+        for (id, term) in self.nodes.iter().enumerate() {
+            let shape = if term.is_normal_form() { "doublecircle" } else { "ellipse" };
+            writeln!(dot, "  n{} [label=\"{}\", shape={}];", id, escape(&term.to_string()), shape).unwrap();
+        }
+        for edge in &self.edges {
+            writeln!(dot, "  n{} -> n{} [label=\"{}\"];", edge.from, edge.to, path_label(&edge.redex)).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a JSON object with `nodes`, `edges` and `complete` fields
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<String> = self.nodes
+            .iter()
+            .enumerate()
+            .map(|(id, term)| {
+                format!(
+                    "{{\"id\": {}, \"term\": \"{}\", \"normal\": {}}}",
+                    id, escape(&term.to_string()), term.is_normal_form()
+                )
+            })
+            .collect();
+        let edges: Vec<String> = self.edges
+            .iter()
+            .map(|edge| {
+                let redex: Vec<String> = edge.redex.iter().map(|dir| format!("\"{:?}\"", dir)).collect();
+                format!("{{\"from\": {}, \"to\": {}, \"redex\": [{}]}}", edge.from, edge.to, redex.join(", "))
+            })
+            .collect();
+
+        format!(
+            "{{\"nodes\": [{}], \"edges\": [{}], \"complete\": {}}}",
+            nodes.join(", "), edges.join(", "), self.complete
+        )
+    }
+}
+
+/// Escapes a string for a double-quoted DOT or JSON string
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn path_label(path: &[Dir]) -> String {
+    if path.is_empty() {
+        return "root".to_string();
+    }
+
+    let dirs: Vec<String> = path.iter().map(|dir| format!("{:?}", dir)).collect();
+    dirs.join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_one;
+
+    fn explore(text: &str, limit: usize) -> ReductionGraph {
+        ReductionGraph::explore(&parse_one(text).unwrap(), limit)
+    }
+
+    #[test]
+    fn test_normal_form_has_single_node() {
+        let graph = explore(r"\x.x", 10);
+        assert_eq!(1, graph.nodes.len());
+        assert!(graph.edges.is_empty());
+        assert!(graph.complete);
+    }
+
+    #[test]
+    fn test_diverging_argument_loops_back_to_same_node() {
+        let graph = explore(r"(\x.y) ((\x.x x) (\x.x x))", 10);
+        assert_eq!(2, graph.nodes.len());
+        assert_eq!(vec![1], graph.normal_forms());
+        assert!(graph.edges.iter().any(|edge| edge.from == 0 && edge.to == 0));
+        assert!(graph.complete);
+    }
+
+    #[test]
+    fn test_all_reductions_meet_at_single_normal_form() {
+        let graph = explore(r"(\x.\a.x x) ((\y.y) z)", 10);
+        assert_eq!(6, graph.nodes.len());
+        assert_eq!(vec![5], graph.normal_forms());
+        assert_eq!(7, graph.edges.len());
+    }
+
+    #[test]
+    fn test_finds_alpha_equivalent_node() {
+        let graph = explore(r"(\x.\y.y) z", 10);
+        assert_eq!(Some(1), graph.find(&parse_one(r"\a.a").unwrap()));
+    }
+
+    #[test]
+    fn test_stops_at_node_limit() {
+        let graph = explore(r"(\x.x x x) (\x.x x x)", 3);
+        assert_eq!(3, graph.nodes.len());
+        assert!(!graph.complete);
+    }
+
+    #[test]
+    fn test_renders_dot() {
+        assert_eq!(
+            "digraph reductions {\n  n0 [label=\"(λx.x) y\", shape=ellipse];\n  n1 [label=\"y\", shape=doublecircle];\n  n0 -> n1 [label=\"root\"];\n}\n",
+            explore(r"(\x.x) y", 10).to_dot()
+        );
+    }
+
+    #[test]
+    fn test_renders_json() {
+        assert_eq!(
+            "{\"nodes\": [{\"id\": 0, \"term\": \"(λx.x) y\", \"normal\": false}, {\"id\": 1, \"term\": \"y\", \"normal\": true}], \
+             \"edges\": [{\"from\": 0, \"to\": 1, \"redex\": []}], \"complete\": true}",
+            explore(r"(\x.x) y", 10).to_json()
+        );
+    }
+}
@@ -0,0 +1,40 @@
+#![feature(box_syntax, box_patterns, bind_by_move_pattern_guards)]
+
+//! An untyped lambda calculus interpreter, with a System Fω type checker.
+//!
+//! Terms are parsed with [`parser`] into the named representation, and can be converted to
+//! De Bruijn indices with [`notation::conversion`]. The reduction strategies in [`evaluation`]
+//! all implement the `Evaluable` trait, apart from the abstract machines and big-step evaluators,
+//! which evaluate a term in one call. Operations on terms, like substitution and eta conversion,
+//! come from the traits re-exported at the top level.
+//!
+//! ```
+//! use lambda_calculus::evaluation::{EmptyContext, Evaluable, Normal};
+//! use lambda_calculus::parser::parse_one;
+//!
+//! let term = parse_one(r"(\x.x) y").unwrap();
+//! let result = Normal::new().evaluate(&mut EmptyContext{}, term);
+//! assert_eq!(parse_one("y").unwrap(), result);
+//! ```
+
+#[macro_use]
+mod macros;
+
+pub mod evaluation;
+pub mod graph;
+pub mod notation;
+pub mod parser;
+pub mod path;
+pub mod redex;
+pub mod types;
+
+mod eta;
+mod substitution;
+mod vars;
+
+pub use eta::{is_beta_eta_equivalent, Eta};
+pub use notation::{DeBruijn, Named};
+pub use notation::conversion::{remove_names, restore_names, ConversionError};
+pub use parser::{parse, parse_de_bruijn, parse_one, ParseError};
+pub use substitution::{NamingScheme, NumericSuffixes, Primes, Substitutable};
+pub use vars::Variables;
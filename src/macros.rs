@@ -0,0 +1,165 @@
+//! The `lambda!` macro, for writing terms in Rust code
+//!
+//! Rust's tokenizer doesn't accept `\`, so abstractions use closure syntax instead: `|x, y| body`
+//! is `λx.λy.body`. Everything else is as in the parser's syntax: application is juxtaposition and
+//! is left associative, an abstraction extends as far right as possible, and parentheses group.
+//! A Rust expression in braces is spliced in as a subterm, so larger terms can be built from
+//! smaller ones:
+//!
+//! ```
+//! use lambda_calculus::lambda;
+//!
+//! let id = lambda!(|x| x);
+//! let twice = lambda!(|f, x| f (f x));
+//! let term = lambda!({twice} {id} y);
+//! ```
+//!
+//! Malformed terms are compile errors, since no rule of the macro matches them.
+//!
+//! The macro expands recursively: each level of parentheses and each argument of an application
+//! costs a level of macro recursion. A term that nests more deeply than about a hundred levels,
+//! such as a large Church numeral written out in full, needs a higher limit in the crate using
+//! the macro, e.g. `#![recursion_limit = "256"]`.
+
+#[macro_export]
+macro_rules! lambda {
+    // Abstractions
+    (| $($name:ident),+ | $($body:tt)+) => {
+        $crate::lambda!(@abstraction [$($name)+] $($body)+)
+    };
+    (@abstraction [$name:ident $($rest:ident)*] $($body:tt)+) => {
+        $crate::notation::named::Term::Abstraction(
+            stringify!($name).to_string(),
+            Box::new($crate::lambda!(@abstraction [$($rest)*] $($body)+))
+        )
+    };
+    (@abstraction [] $($body:tt)+) => {
+        $crate::lambda!($($body)+)
+    };
+
+    // Applications, accumulating the function applied so far
+    (@application ($function:expr)) => {
+        $function
+    };
+    (@application ($function:expr) | $($rest:tt)+) => {
+        $crate::notation::named::Term::Application(
+            Box::new($function),
+            Box::new($crate::lambda!(| $($rest)+))
+        )
+    };
+    // A parenthesized argument goes straight back to `@application`, so each level of nesting
+    // costs one level of macro recursion rather than three
+    (@application ($function:expr) (| $($inner:tt)+) $($rest:tt)*) => {
+        $crate::lambda!(@application (
+            $crate::notation::named::Term::Application(
+                Box::new($function),
+                Box::new($crate::lambda!(| $($inner)+))
+            )
+        ) $($rest)*)
+    };
+    (@application ($function:expr) ($first:tt $($inner:tt)*) $($rest:tt)*) => {
+        $crate::lambda!(@application (
+            $crate::notation::named::Term::Application(
+                Box::new($function),
+                Box::new($crate::lambda!(@application ($crate::lambda!(@atom $first)) $($inner)*))
+            )
+        ) $($rest)*)
+    };
+    (@application ($function:expr) $argument:tt $($rest:tt)*) => {
+        $crate::lambda!(@application (
+            $crate::notation::named::Term::Application(
+                Box::new($function),
+                Box::new($crate::lambda!(@atom $argument))
+            )
+        ) $($rest)*)
+    };
+
+    // Terms that don't need to be split further
+    (@atom $name:ident) => {
+        $crate::notation::named::Term::Variable(stringify!($name).to_string())
+    };
+    (@atom (| $($inner:tt)+)) => {
+        $crate::lambda!(| $($inner)+)
+    };
+    (@atom ($first:tt $($inner:tt)*)) => {
+        $crate::lambda!(@application ($crate::lambda!(@atom $first)) $($inner)*)
+    };
+    (@atom {$splice:expr}) => {
+        $crate::notation::named::Term::from($splice)
+    };
+
+    ($first:tt $($rest:tt)*) => {
+        $crate::lambda!(@application ($crate::lambda!(@atom $first)) $($rest)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notation::named::{Term, l, a};
+    use crate::parser::parse_one;
+
+    fn parse(text: &str) -> Term {
+        parse_one(text).unwrap()
+    }
+
+    #[test]
+    fn test_builds_variable() {
+        assert_eq!(parse("x"), lambda!(x));
+    }
+
+    #[test]
+    fn test_builds_abstraction_with_several_variables() {
+        assert_eq!(parse(r"\x.\y.x"), lambda!(|x, y| x));
+    }
+
+    #[test]
+    fn test_builds_left_associative_application() {
+        assert_eq!(parse(r"f x (g y) z"), lambda!(f x (g y) z));
+    }
+
+    #[test]
+    fn test_abstraction_extends_to_the_right() {
+        assert_eq!(parse(r"f \x.x y"), lambda!(f |x| x y));
+    }
+
+    #[test]
+    fn test_builds_parenthesized_abstraction() {
+        assert_eq!(parse(r"(\x.x) (\y.y) z"), lambda!((|x| x) (|y| y) z));
+    }
+
+    #[test]
+    fn test_splices_rust_terms() {
+        let id = lambda!(|x| x);
+        let twice = l("f", l("x", a("f", a("f", "x"))));
+        assert_eq!(
+            parse(r"(\f.\x.f (f x)) (\x.x) y"),
+            lambda!({twice} {id} y)
+        );
+    }
+
+    #[test]
+    fn test_debug_prints_parser_syntax() {
+        assert_eq!(r"Named(\x y.x (y x))", format!("{:?}", lambda!(|x, y| x (y x))));
+        assert_eq!(r#"l("x", a("x", "y"))"#, format!("{:#?}", lambda!(|x| x y)));
+    }
+
+    #[test]
+    fn test_builds_church_numeral_addition() {
+        assert_eq!(
+            parse(r"\m.\n.\s.\z.m s (n s z)"),
+            lambda!(|m, n, s, z| m s (n s z))
+        );
+    }
+
+    #[test]
+    fn test_builds_deeply_nested_church_numeral() {
+        let c50 = lambda!(|s, z|
+            s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (
+            s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (s (z
+            )))))))))))))))))))))))))
+            )))))))))))))))))))))))))
+        );
+        let numeral = format!(r"\s.\z.{}z{}", "s (".repeat(50), ")".repeat(50));
+        assert_eq!(parse(&numeral), c50);
+    }
+}
@@ -1,31 +1,255 @@
-#![feature(box_syntax, box_patterns, bind_by_move_pattern_guards)]
+#![feature(box_syntax)]
 
+use std::collections::HashMap;
+use std::env;
 use std::io::{self, Read};
 
-mod evaluation;
-mod notation;
-mod parser;
-mod substitution;
-mod vars;
+use failure::{
+    Fail,
+};
 
-use evaluation::{
-    Normal as Strategy,
+use lambda_calculus::evaluation::{
+    Applicative,
+    BetaEta,
+    CallByNeed,
+    CallByValue,
+    Cek,
     EmptyContext,
     Evaluable,
+    Full,
+    HeadNormal,
+    HeadSpine,
+    Heap,
+    HybridApplicative,
+    HybridNormal,
+    Krivine,
+    Lazy,
+    LocallyNamelessNormal,
+    Natural,
+    Nbe,
+    Normal,
+    SharedNormal,
+    Stats,
+    WeakHeadNormal,
+    evaluate_with_stats,
 };
+use lambda_calculus::graph::ReductionGraph;
+use lambda_calculus::notation::{DeBruijn, LocallyNameless};
+use lambda_calculus::notation::conversion::{remove_names, restore_names, sorted_free_variables};
+use lambda_calculus::notation::shared::Store;
+use lambda_calculus::notation::named::Term;
+use lambda_calculus::notation::pretty::Pretty;
+use lambda_calculus::notation::render::Renderer;
+use lambda_calculus::{parser, types};
+
+#[derive(Debug, Fail)]
+enum UsageError {
+    #[fail(display = "unknown strategy: {} (expected one of: {})", _0, _1)]
+    UnknownStrategy(String, &'static str),
+
+    #[fail(display = "missing value for {}", _0)]
+    MissingValue(&'static str),
+
+    #[fail(display = "unknown format: {} (expected text, ascii, tree or dot)", _0)]
+    UnknownFormat(String),
+
+    #[fail(display = "invalid width: {}", _0)]
+    InvalidWidth(String),
+
+    #[fail(display = "unknown graph format: {} (expected dot or json)", _0)]
+    UnknownGraphFormat(String),
+
+    #[fail(display = "invalid graph limit: {}", _0)]
+    InvalidGraphLimit(String),
+
+    #[fail(display = "no statistics for strategy: {} (it doesn't step named terms)", _0)]
+    NoStats(String),
+}
+
+/// Maximum number of terms to include in a reduction graph, unless `--graph-limit` is given
+const GRAPH_LIMIT: usize = 100;
+
+const STRATEGIES: &str = "normal, beta-eta, call-by-value, lazy, call-by-need, locally-nameless, shared, full, applicative, hybrid-applicative, head-spine, hybrid-normal, head-normal, weak-head-normal, natural-call-by-value, natural-call-by-name, krivine, cek, nbe";
+
+/// Returns a function that evaluates terms with the named strategy
+fn strategy(name: &str) -> Result<Box<dyn Fn(Term) -> Term>, UsageError> {
+    let evaluate: Box<dyn Fn(Term) -> Term> = match name {
+        "normal" => box |term| Normal::new().evaluate(&mut EmptyContext{}, term),
+        "call-by-value" => box |term| CallByValue::new().evaluate(&mut EmptyContext{}, term),
+        "lazy" => box |term| Lazy::new().evaluate(&mut EmptyContext{}, term),
+        "call-by-need" => box |term| CallByNeed::new().evaluate(&mut Heap::new(), term),
+        "locally-nameless" => box |term| {
+            let term = LocallyNamelessNormal::new().evaluate(&mut EmptyContext{}, LocallyNameless::from(&term));
+            Term::from(&term)
+        },
+        "shared" => box |term| {
+            let free_variables = sorted_free_variables(&term);
+            let mut store = Store::new();
+            let term = store.import(&remove_names(&term, &free_variables).expect("every free variable is in the naming context"));
+            let term = SharedNormal::new().evaluate(&mut store, term);
+            restore_names(&DeBruijn::from(&term), &free_variables).expect("evaluation doesn't introduce free variables")
+        },
+        "full" => box |term| Full::new().evaluate(&mut EmptyContext{}, term),
+        "beta-eta" => box |term| BetaEta::new().evaluate(&mut EmptyContext{}, term),
+        "applicative" => box |term| Applicative::new().evaluate(&mut EmptyContext{}, term),
+        "hybrid-applicative" => box |term| HybridApplicative::new().evaluate(&mut EmptyContext{}, term),
+        "head-spine" => box |term| HeadSpine::new().evaluate(&mut EmptyContext{}, term),
+        "hybrid-normal" => box |term| HybridNormal::new().evaluate(&mut EmptyContext{}, term),
+        "head-normal" => box |term| HeadNormal::new().evaluate(&mut EmptyContext{}, term),
+        "weak-head-normal" => box |term| WeakHeadNormal::new().evaluate(&mut EmptyContext{}, term),
+        "natural-call-by-value" => box |term| Natural::call_by_value().evaluate(&term),
+        "natural-call-by-name" => box |term| Natural::call_by_name().evaluate(&term),
+        "krivine" => box |term| Krivine::new().evaluate(&term),
+        "cek" => box |term| Cek::new().evaluate(&term),
+        "nbe" => box |term| Nbe::new().normalize(&term),
+        _ => return Err(UsageError::UnknownStrategy(name.to_string(), STRATEGIES)),
+    };
+    Ok(evaluate)
+}
+
+/// Evaluates a term, given the macros it was parsed with, and returns statistics as well
+type EvaluateWithStats = Box<dyn Fn(Term, &HashMap<&str, Term>) -> (Term, Stats)>;
+
+/// Returns a function that evaluates terms with the named strategy, also returning statistics
+/// about the evaluation. Only the strategies that step named terms can report them.
+fn strategy_with_stats(name: &str) -> Result<EvaluateWithStats, UsageError> {
+    let evaluate: EvaluateWithStats = match name {
+        "normal" => box |term, macros| evaluate_with_stats(&Normal::new(), &mut EmptyContext{}, term, macros),
+        "call-by-value" => box |term, macros| evaluate_with_stats(&CallByValue::new(), &mut EmptyContext{}, term, macros),
+        "lazy" => box |term, macros| evaluate_with_stats(&Lazy::new(), &mut EmptyContext{}, term, macros),
+        "call-by-need" => box |term, macros| evaluate_with_stats(&CallByNeed::new(), &mut Heap::new(), term, macros),
+        "full" => box |term, macros| evaluate_with_stats(&Full::new(), &mut EmptyContext{}, term, macros),
+        "beta-eta" => box |term, macros| evaluate_with_stats(&BetaEta::new(), &mut EmptyContext{}, term, macros),
+        "applicative" => box |term, macros| evaluate_with_stats(&Applicative::new(), &mut EmptyContext{}, term, macros),
+        "hybrid-applicative" => box |term, macros| evaluate_with_stats(&HybridApplicative::new(), &mut EmptyContext{}, term, macros),
+        "head-spine" => box |term, macros| evaluate_with_stats(&HeadSpine::new(), &mut EmptyContext{}, term, macros),
+        "hybrid-normal" => box |term, macros| evaluate_with_stats(&HybridNormal::new(), &mut EmptyContext{}, term, macros),
+        "head-normal" => box |term, macros| evaluate_with_stats(&HeadNormal::new(), &mut EmptyContext{}, term, macros),
+        "weak-head-normal" => box |term, macros| evaluate_with_stats(&WeakHeadNormal::new(), &mut EmptyContext{}, term, macros),
+        // Unknown strategies are reported as such, rather than as lacking statistics
+        _ => return Err(match strategy(name) {
+            Ok(_) => UsageError::NoStats(name.to_string()),
+            Err(error) => error,
+        }),
+    };
+    Ok(evaluate)
+}
 
 fn main() -> Result<(), failure::Error> {
-    let mut parser = parser::Parser::new();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let typed = args.iter().any(|arg| arg == "--typed");
+    let compare_sharing = args.iter().any(|arg| arg == "--compare-sharing");
+    let strategy_name = match args.iter().position(|arg| arg == "--strategy") {
+        Some(i) => args.get(i + 1).ok_or(UsageError::MissingValue("--strategy"))?.as_str(),
+        None => "normal",
+    };
+    let evaluate = strategy(strategy_name)?;
+    let evaluate_with_stats = if args.iter().any(|arg| arg == "--stats") {
+        Some(strategy_with_stats(strategy_name)?)
+    } else {
+        None
+    };
+    let graph_format = match args.iter().position(|arg| arg == "--graph") {
+        Some(i) => match args.get(i + 1).ok_or(UsageError::MissingValue("--graph"))?.as_str() {
+            format @ "dot" | format @ "json" => Some(format),
+            format => return Err(UsageError::UnknownGraphFormat(format.to_string()).into()),
+        },
+        None => None,
+    };
+    let graph_limit = match args.iter().position(|arg| arg == "--graph-limit") {
+        Some(i) => {
+            let limit = args.get(i + 1).ok_or(UsageError::MissingValue("--graph-limit"))?;
+            limit.parse().map_err(|_| UsageError::InvalidGraphLimit(limit.clone()))?
+        },
+        None => GRAPH_LIMIT,
+    };
+
+    let format = match args.iter().position(|arg| arg == "--format") {
+        Some(i) => args.get(i + 1).ok_or(UsageError::MissingValue("--format"))?.as_str(),
+        None => "text",
+    };
+    let renderer = if args.iter().any(|arg| arg == "--back-edges") {
+        Renderer::with_back_edges()
+    } else {
+        Renderer::new()
+    };
+    let pretty = match args.iter().position(|arg| arg == "--width") {
+        Some(i) => {
+            let width = args.get(i + 1).ok_or(UsageError::MissingValue("--width"))?;
+            Pretty::with_width(width.parse().map_err(|_| UsageError::InvalidWidth(width.clone()))?)
+        },
+        None => Pretty::new(),
+    };
+    let pretty = if format == "ascii" { pretty.ascii() } else { pretty };
+    let print: Box<dyn Fn(&Term)> = match format {
+        "text" | "ascii" => box move |term| println!("{}", pretty.print(term)),
+        "tree" => box move |term| print!("{}", renderer.to_tree(term)),
+        "dot" => box move |term| print!("{}", renderer.to_dot(term)),
+        _ => return Err(UsageError::UnknownFormat(format.to_string()).into()),
+    };
 
     let mut text = String::new();
     io::stdin().read_to_string(&mut text).unwrap();
-    parser
-        .parse(&text)
-        .map(|result| {
-            for term in result {
-                // TODO maybe have parser terms maintain the slice from which they came, so we
-                // could write something like (expr = result)
-                println!("{}", Strategy::new().evaluate(&mut EmptyContext{}, term));
-            }
-        })
+
+    if typed {
+        return evaluate_typed(&text, &evaluate);
+    }
+
+    let mut parser = parser::Parser::new();
+    let terms: Vec<Term> = parser.parse(&text)?.collect();
+    for term in terms {
+        // TODO maybe have parser terms maintain the slice from which they came, so we
+        // could write something like (expr = result)
+        if let Some(format) = graph_format {
+            print_reduction_graph(&term, format, graph_limit);
+        } else if compare_sharing {
+            print_sharing_comparison(term);
+        } else if let Some(evaluate_with_stats) = &evaluate_with_stats {
+            let (result, stats) = evaluate_with_stats(term, parser.macros());
+            print(&result);
+            print_stats(&stats);
+        } else {
+            print(&evaluate(term));
+        }
+    }
+    Ok(())
+}
+
+/// Prints the reduction graph of a term as DOT or JSON, with at most `limit` terms. A graph that
+/// was cut off is reported on stderr, so the output stays valid DOT or JSON.
+fn print_reduction_graph(term: &Term, format: &str, limit: usize) {
+    let graph = ReductionGraph::explore(term, limit);
+    match format {
+        "dot" => print!("{}", graph.to_dot()),
+        _ => println!("{}", graph.to_json()),
+    }
+    if !graph.complete {
+        eprintln!("reduction graph of {} cut off at {} terms (raise --graph-limit to explore further)", term, limit);
+    }
+}
+
+/// Evaluates a term with both the lazy and call by need strategies, showing how many steps
+/// sharing saves
+fn print_sharing_comparison(term: Term) {
+    let (_, lazy_steps) = Lazy::new().evaluate_counting_steps(&mut EmptyContext{}, term.clone());
+    let (result, need_steps) = CallByNeed::new().evaluate_counting_steps(&mut Heap::new(), term);
+    println!("{}", result);
+    println!("  lazy: {} steps, call-by-need: {} steps", lazy_steps, need_steps);
+}
+
+/// Prints the statistics for an evaluation under the term it evaluated to
+fn print_stats(stats: &Stats) {
+    for line in stats.to_string().lines() {
+        println!("  {}", line);
+    }
+}
+
+/// Type checks each term as a System Fω term, and then evaluates its type erasure
+fn evaluate_typed(text: &str, evaluate: &dyn Fn(Term) -> Term) -> Result<(), failure::Error> {
+    let mut parser = types::parser::Parser::new();
+    for term in parser.parse(text)? {
+        let ty = types::type_of(&term)?;
+        println!("{} : {}", evaluate(term.erase()), ty.normalize());
+    }
+    Ok(())
 }
@@ -1,3 +1,12 @@
+//! Conversion between named and De Bruijn terms
+//!
+//! Free variables are given indices from a naming context: a list of names, where the last name
+//! has index 0 at the top level of the term. Passing the context explicitly, for example the names
+//! of a table of global definitions, keeps the indices stable between terms and between runs.
+use failure::{
+    Fail,
+};
+
 use crate::vars::Variables;
 
 use super::{
@@ -6,38 +15,150 @@ use super::{
     Notation,
 };
 
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum ConversionError {
+    #[fail(display = "variable {} is not in the naming context", _0)]
+    UnboundVariable(String),
+
+    #[fail(display = "index {} is outside the naming context", _0)]
+    UnboundIndex(u32),
+}
+
+/// Work left to do while converting a term with an explicit stack instead of recursion, where `B`
+/// is what the converted abstraction needs to know about its binder
+enum Work<'t, T, B> {
+    Enter(&'t T),
+    ExitAbstraction(B),
+    ExitApplication,
+}
+
 struct RemoveNames {
     naming_context: Vec<<Named as Notation>::VariableName>,
 }
 
 impl RemoveNames {
-    pub fn call(&mut self, named: &Named) -> DeBruijn {
-        match named {
-            Named::Variable(s) => {
-                DeBruijn::Variable(
-                    self.naming_context
+    pub fn call(&mut self, named: &Named) -> Result<DeBruijn, ConversionError> {
+        let mut results = Vec::new();
+        let mut work = vec![Work::Enter(named)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(Named::Variable(s)) => {
+                    let index = self.naming_context
                         .iter()
                         .rev()
                         .position(|x| x == s)
-                        .unwrap() as u32
-                )
-            },
-            Named::Abstraction(arg, body) => {
-                self.naming_context.push(arg.clone());
-                let abs = DeBruijn::Abstraction(box self.call(body));
-                self.naming_context.pop();
-                abs
-            },
-            Named::Application(t1, t2) => {
-                DeBruijn::Application(box self.call(t1), box self.call(t2))
-            },
+                        .ok_or_else(|| ConversionError::UnboundVariable(s.clone()))?;
+                    results.push(DeBruijn::Variable(index as u32));
+                },
+                Work::Enter(Named::Abstraction(arg, body)) => {
+                    self.naming_context.push(arg.clone());
+                    work.push(Work::ExitAbstraction(()));
+                    work.push(Work::Enter(body));
+                },
+                Work::Enter(Named::Application(t1, t2)) => {
+                    work.push(Work::ExitApplication);
+                    work.push(Work::Enter(t2));
+                    work.push(Work::Enter(t1));
+                },
+                Work::ExitAbstraction(()) => {
+                    self.naming_context.pop();
+                    let body = results.pop().expect("abstraction has a body");
+                    results.push(DeBruijn::Abstraction(box body));
+                },
+                Work::ExitApplication => {
+                    let t2 = results.pop().expect("application has an argument");
+                    let t1 = results.pop().expect("application has a function");
+                    results.push(DeBruijn::Application(box t1, box t2));
+                },
+            }
+        }
+        Ok(results.pop().expect("term has a result"))
+    }
+}
+
+struct RestoreNames {
+    naming_context: Vec<<Named as Notation>::VariableName>,
+}
+
+impl RestoreNames {
+    pub fn call(&mut self, term: &DeBruijn) -> Result<Named, ConversionError> {
+        let mut results = Vec::new();
+        let mut work = vec![Work::Enter(term)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(DeBruijn::Variable(index)) => {
+                    let name = self.naming_context
+                        .iter()
+                        .rev()
+                        .nth(*index as usize)
+                        .ok_or(ConversionError::UnboundIndex(*index))?;
+                    results.push(Named::Variable(name.clone()));
+                },
+                Work::Enter(DeBruijn::Abstraction(body)) => {
+                    let name = self.fresh_name();
+                    self.naming_context.push(name.clone());
+                    work.push(Work::ExitAbstraction(name));
+                    work.push(Work::Enter(body));
+                },
+                Work::Enter(DeBruijn::Application(t1, t2)) => {
+                    work.push(Work::ExitApplication);
+                    work.push(Work::Enter(t2));
+                    work.push(Work::Enter(t1));
+                },
+                Work::ExitAbstraction(name) => {
+                    self.naming_context.pop();
+                    let body = results.pop().expect("abstraction has a body");
+                    results.push(Named::Abstraction(name, box body));
+                },
+                Work::ExitApplication => {
+                    let t2 = results.pop().expect("application has an argument");
+                    let t1 = results.pop().expect("application has a function");
+                    results.push(Named::Application(box t1, box t2));
+                },
+            }
         }
+        Ok(results.pop().expect("term has a result"))
+    }
+
+    /// Returns the first name in `a`, `b`, ..., `z`, `a'`, `b'`, ... that isn't in the naming context
+    fn fresh_name(&self) -> String {
+        (0..)
+            .map(|i| {
+                let letter = (b'a' + (i % 26) as u8) as char;
+                letter.to_string() + &"'".repeat(i / 26)
+            })
+            .find(|name| !self.naming_context.contains(name))
+            .unwrap()
     }
 }
 
+/// Converts a named term into a De Bruijn term, where the last name in `free_variables` has the
+/// lowest index at the top level. Fails if a free variable of the term isn't in `free_variables`.
+pub fn remove_names(named: &Named, free_variables: &[String]) -> Result<DeBruijn, ConversionError> {
+    RemoveNames { naming_context: free_variables.to_vec() }.call(named)
+}
+
+/// Converts a De Bruijn term into a named term, the inverse of `remove_names`. Fails if a free
+/// index is past the end of `free_variables`.
+///
+/// Bound variables are given the first available name that doesn't conflict with any other
+/// name in scope.
+pub fn restore_names(term: &DeBruijn, free_variables: &[String]) -> Result<Named, ConversionError> {
+    RestoreNames { naming_context: free_variables.to_vec() }.call(term)
+}
+
+/// Returns the free variables of a term in sorted order, as a naming context that doesn't depend
+/// on anything but the term itself
+pub fn sorted_free_variables(named: &Named) -> Vec<String> {
+    let mut free_variables: Vec<String> = named.free_variables().into_iter().collect();
+    free_variables.sort();
+    free_variables
+}
+
 impl From<&Named> for DeBruijn {
+    /// Free variables are numbered in sorted order of their names
     fn from(named: &Named) -> DeBruijn {
-        RemoveNames { naming_context: named.free_variables().into_iter().collect() }.call(named)
+        remove_names(named, &sorted_free_variables(named)).expect("every free variable is in the naming context")
     }
 }
 
@@ -45,6 +166,9 @@ impl From<&Named> for DeBruijn {
 mod test {
     use crate::notation::de_bruijn::{l, a};
     use crate::notation::named::{l as nl, a as na};
+    use super::{remove_names, restore_names, ConversionError};
+    use crate::notation::{DeBruijn, Named};
+    use crate::parser::parse_one;
 
     #[test]
     pub fn test_abstraction_with_bound_variables() {
@@ -61,4 +185,47 @@ mod test {
             (&nl("x", nl("y", na(na("x", "y"), "z")))).into()
         );
     }
+
+    #[test]
+    pub fn test_remove_names_with_explicit_context() {
+        assert_eq!(
+            l(a(a(0u32, 1u32), 2u32)),
+            remove_names(&nl("x", na(na("x", "z"), "y")), &["y".to_string(), "z".to_string()]).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_restore_names_avoids_free_variables() {
+        assert_eq!(
+            nl("b", nl("c", na(na("b", "c"), "a"))),
+            restore_names(&l(l(a(a(1u32, 0u32), 2u32))), &["a".to_string()]).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_remove_names_fails_for_variable_missing_from_context() {
+        assert_eq!(
+            Err(ConversionError::UnboundVariable("z".to_string())),
+            remove_names(&nl("x", na("x", "z")), &["y".to_string()])
+        );
+    }
+
+    #[test]
+    pub fn test_restore_names_fails_for_index_outside_context() {
+        assert_eq!(
+            Err(ConversionError::UnboundIndex(2)),
+            restore_names(&l(a(0u32, 2u32)), &["a".to_string()])
+        );
+    }
+
+    #[test]
+    pub fn test_free_variables_are_numbered_in_sorted_order() {
+        let named: Named = parse_one("c a d b").unwrap();
+        for _ in 0..10 {
+            assert_eq!(
+                a(a(a(1u32, 3u32), 0u32), 2u32),
+                DeBruijn::from(&named)
+            );
+        }
+    }
 }
@@ -1,10 +1,12 @@
 use std::{
+    collections::HashSet,
     fmt,
+    mem,
 };
 
-use super::Notation;
+use super::{generic, Node, Notation};
+use super::pretty::Pretty;
 
-#[derive(Clone, PartialEq)]
 pub enum Term {
     Variable(u32),
     Abstraction(Box<Term>),
@@ -13,11 +15,18 @@ pub enum Term {
 
 impl Term {
     pub fn is_redex(&self) -> bool {
-        match self {
-            Term::Application(box Term::Abstraction(_), _) => true,
-            Term::Application(t1, t2) => t1.is_redex() || t2.is_redex(),
-            _ => false,
+        let mut work = vec![self];
+        while let Some(term) = work.pop() {
+            match term {
+                Term::Application(box Term::Abstraction(_), _) => return true,
+                Term::Application(t1, t2) => {
+                    work.push(t2);
+                    work.push(t1);
+                },
+                _ => {},
+            }
         }
+        false
     }
 
     pub fn is_value(&self) -> bool {
@@ -26,18 +35,146 @@ impl Term {
             _ => false,
         }
     }
+
+    /// Returns true if this term contains no redexes, including inside abstractions
+    pub fn is_normal_form(&self) -> bool {
+        let mut work = vec![self];
+        while let Some(term) = work.pop() {
+            match term {
+                Term::Variable(_) => {},
+                Term::Abstraction(body) => work.push(body),
+                Term::Application(box Term::Abstraction(_), _) => return false,
+                Term::Application(t1, t2) => {
+                    work.push(t2);
+                    work.push(t1);
+                },
+            }
+        }
+        true
+    }
+
+    /// Returns true if this term has the form `λx1...λxn.y t1 ... tm`, so that no reduction of
+    /// it can change its head variable
+    pub fn is_head_normal_form(&self) -> bool {
+        let mut term = self;
+        while let Term::Abstraction(body) = term {
+            term = body;
+        }
+        term.has_variable_head()
+    }
+
+    /// Returns true if this term is an abstraction or has the form `y t1 ... tm`
+    pub fn is_whnf(&self) -> bool {
+        match self {
+            Term::Abstraction(_) => true,
+            _ => self.has_variable_head(),
+        }
+    }
+
+    /// Returns true if this term is a variable applied to zero or more arguments
+    fn has_variable_head(&self) -> bool {
+        let mut term = self;
+        while let Term::Application(t1, _) = term {
+            term = t1;
+        }
+        matches!(term, Term::Variable(_))
+    }
+
+    /// Returns this term with all free variable indices increased by `amount`
+    pub fn shift(&self, amount: u32) -> Term {
+        generic::rebuild(self, &mut |term, binders| match term {
+            Term::Variable(index) if *index >= binders.len() as u32 => Some(Term::Variable(index + amount)),
+            _ => None,
+        })
+    }
+
+    /// Returns this term with all free variable indices decreased by one, for when the binder of
+    /// index 0 is removed. Index 0 must not be free in this term.
+    pub fn unshift(&self) -> Term {
+        generic::rebuild(self, &mut |term, binders| {
+            let cutoff = binders.len() as u32;
+            match term {
+                Term::Variable(index) if *index > cutoff => Some(Term::Variable(index - 1)),
+                Term::Variable(index) if *index == cutoff => panic!("unshifting a term where {} is free", index),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns true if the variable with the given index (relative to the top level of this term)
+    /// occurs free in this term
+    pub fn has_free_variable(&self, index: u32) -> bool {
+        let mut work = vec![(self, index)];
+        while let Some((term, index)) = work.pop() {
+            match term {
+                Term::Variable(i) if *i == index => return true,
+                Term::Variable(_) => {},
+                Term::Abstraction(t) => work.push((t, index + 1)),
+                Term::Application(t1, t2) => {
+                    work.push((t2, index));
+                    work.push((t1, index));
+                },
+            }
+        }
+        false
+    }
+}
+
+/// Drops the term one node at a time, as named terms are, so that deep terms can be dropped
+impl Drop for Term {
+    fn drop(&mut self) {
+        let mut work = Vec::new();
+        take_subterms(self, &mut work);
+        while let Some(mut term) = work.pop() {
+            take_subterms(&mut term, &mut work);
+        }
+    }
+}
+
+/// Moves the compound subterms of `term` onto `work`, leaving variables behind
+fn take_subterms(term: &mut Term, work: &mut Vec<Term>) {
+    let mut take = |subterm: &mut Box<Term>| {
+        if let Term::Variable(_) = **subterm {
+            return;
+        }
+        work.push(mem::replace(&mut **subterm, Term::Variable(0)));
+    };
+    match term {
+        Term::Variable(_) => {},
+        Term::Abstraction(body) => {
+            take(body);
+        },
+        Term::Application(t1, t2) => {
+            take(t1);
+            take(t2);
+        },
+    }
+}
+
+/// Copies the term without recursing, so that deep terms can be cloned
+impl Clone for Term {
+    fn clone(&self) -> Term {
+        generic::rebuild(self, &mut |_, _| None)
+    }
+}
+
+impl PartialEq for Term {
+    fn eq(&self, other: &Term) -> bool {
+        generic::equal(self, other)
+    }
 }
 
 impl Notation for Term {
     type VariableName = u32;
+    type Binder = ();
 
     /// Returns a term representing the application of `arg` to `func`
     fn application(func: Self, arg: Self) -> Self {
         Term::Application(box func, box arg)
     }
 
-    /// Returns an abstraction term with the given body and bound variable name
-    fn abstraction(_bound_var_name: Self::VariableName, body: Self) -> Self {
+    /// Returns an abstraction term with the given body
+    fn abstraction(_binder: Self::Binder, body: Self) -> Self {
         Term::Abstraction(box body)
     }
 
@@ -45,27 +182,57 @@ impl Notation for Term {
     fn variable(name: Self::VariableName) -> Self {
         Term::Variable(name)
     }
+
+    fn view(&self) -> Node<'_, Self, &Self> {
+        match self {
+            Term::Variable(index) => Node::Variable(index),
+            Term::Abstraction(body) => Node::Abstraction(&(), body),
+            Term::Application(t1, t2) => Node::Application(t1, t2),
+        }
+    }
+
+    fn function_mut(&mut self) -> Option<&mut Self> {
+        match self {
+            Term::Application(t1, _) => Some(t1),
+            _ => None,
+        }
+    }
+
+    fn free_name(index: &u32, binders: &[()]) -> Option<u32> {
+        index.checked_sub(binders.len() as u32)
+    }
+
+    fn lift(&self, count: usize) -> Self {
+        self.shift(count as u32)
+    }
+
+    /// Indices can't be captured, so binders never need renaming
+    fn avoid_capture(_binder: &(), _body: &Self, _free: &HashSet<u32>) -> Option<((), Self)> {
+        None
+    }
+
+    fn instantiate(_binder: &(), body: &Self, arg: &Self) -> Self {
+        generic::substitute(body, &0, &arg.shift(1)).unshift()
+    }
 }
 
 impl fmt::Display for Term {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Term::Variable(s) => write!(f, "{}", s),
-            Term::Abstraction(t) => write!(f, "λ.{}", t),
-            Term::Application(box Term::Variable(t1), box Term::Variable(t2)) => write!(f, "{} {}", t1, t2),
-            Term::Application(box Term::Variable(t1), t2) => write!(f, "{} ({})", t1, t2),
-            Term::Application(t1, box Term::Variable(t2)) => write!(f, "({}) {}", t1, t2),
-            Term::Application(t1, t2) => write!(f, "({}) ({})", t1, t2),
-        }
+        write!(f, "{}", Pretty::new().print_de_bruijn(self))
     }
 }
 
+/// Prints the term in the parser's syntax, or with `{:#?}`, as calls to the `l` and `a` helpers
 impl fmt::Debug for Term {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return write!(f, "DeBruijn({})", Pretty::new().ascii().print_de_bruijn(self));
+        }
+
         match self {
-            Term::Variable(s) => write!(f, "{:?}", s),
-            Term::Abstraction(t) => write!(f, "l({:?})", t),
-            Term::Application(t1, t2) => write!(f, "a({:?}, {:?})", t1, t2),
+            Term::Variable(s) => write!(f, "{:#?}", s),
+            Term::Abstraction(t) => write!(f, "l({:#?})", t),
+            Term::Application(t1, t2) => write!(f, "a({:#?}, {:#?})", t1, t2),
         }
     }
 }
@@ -94,3 +261,38 @@ impl <T> From<T> for Term
         Term::Variable(s.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_is_normal_form_false_for_redex_under_abstraction() {
+        assert!(!l(a(0u32, a(l(0u32), 1u32))).is_normal_form());
+    }
+
+    #[test]
+    pub fn test_is_normal_form_true_for_variable_applied_to_abstraction() {
+        assert!(a(0u32, l(a(0u32, 0u32))).is_normal_form());
+    }
+
+    #[test]
+    pub fn test_is_head_normal_form_true_with_redex_in_argument() {
+        assert!(l(a(0u32, a(l(0u32), 1u32))).is_head_normal_form());
+    }
+
+    #[test]
+    pub fn test_is_head_normal_form_false_for_redex_at_head_under_abstraction() {
+        assert!(!l(a(a(l(0u32), 0u32), 1u32)).is_head_normal_form());
+    }
+
+    #[test]
+    pub fn test_is_whnf_true_for_abstraction_with_redex_at_head() {
+        assert!(l(a(l(0u32), 0u32)).is_whnf());
+    }
+
+    #[test]
+    pub fn test_is_whnf_false_for_redex() {
+        assert!(!a(a(l(0u32), 0u32), 1u32).is_whnf());
+    }
+}
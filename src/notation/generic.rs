@@ -0,0 +1,300 @@
+//! Operations written once for every notation
+//!
+//! None of these recurse on the Rust stack: they keep their own stack of work, so they handle
+//! terms that are nested millions of levels deep.
+use std::collections::HashSet;
+
+use crate::substitution::Counts;
+
+use super::{Node, Notation};
+
+/// Returns the names of the variables that occur free in the term
+pub fn free_variables<T: Notation>(term: &T) -> HashSet<T::VariableName> {
+    enum Work<'t, T> {
+        Enter(&'t T),
+        ExitAbstraction,
+    }
+
+    let mut result = HashSet::new();
+    let mut binders = Vec::new();
+    let mut work = vec![Work::Enter(term)];
+    while let Some(item) = work.pop() {
+        match item {
+            Work::Enter(term) => {
+                match term.view() {
+                    Node::Variable(name) => {
+                        result.extend(T::free_name(name, &binders));
+                    },
+                    Node::Abstraction(binder, body) => {
+                        binders.push(binder.clone());
+                        work.push(Work::ExitAbstraction);
+                        work.push(Work::Enter(body));
+                    },
+                    Node::Application(t1, t2) => {
+                        work.push(Work::Enter(t2));
+                        work.push(Work::Enter(t1));
+                    },
+                }
+            },
+            Work::ExitAbstraction => {
+                binders.pop();
+            },
+        }
+    }
+    result
+}
+
+/// Returns the term to put in place of a subterm, given the binders it occurs under, if any
+pub type Replace<'r, T> = dyn FnMut(&T, &[<T as Notation>::Binder]) -> Option<T> + 'r;
+
+/// Returns a copy of the term in which the subterms that `replace` returns a term for are replaced
+/// with that term.
+///
+/// `replace` is called on each subterm, with the binders it occurs under (innermost last), before
+/// any of its own subterms. The subterms of a replaced subterm aren't visited.
+pub fn rebuild<T: Notation>(term: &T, replace: &mut Replace<T>) -> T {
+    rebuild_under(term, Vec::new(), replace)
+}
+
+fn rebuild_under<T: Notation>(term: &T, mut binders: Vec<T::Binder>, replace: &mut Replace<T>) -> T {
+    enum Work<'t, T: Notation> {
+        Enter(&'t T),
+        ExitAbstraction(&'t T::Binder),
+        ExitApplication,
+    }
+
+    let mut results = Vec::new();
+    let mut work = vec![Work::Enter(term)];
+    while let Some(item) = work.pop() {
+        match item {
+            Work::Enter(term) => {
+                if let Some(result) = replace(term, &binders) {
+                    results.push(result);
+                    continue;
+                }
+                match term.view() {
+                    Node::Variable(name) => {
+                        results.push(T::variable(name.clone()));
+                    },
+                    Node::Abstraction(binder, body) => {
+                        binders.push(binder.clone());
+                        work.push(Work::ExitAbstraction(binder));
+                        work.push(Work::Enter(body));
+                    },
+                    Node::Application(t1, t2) => {
+                        work.push(Work::ExitApplication);
+                        work.push(Work::Enter(t2));
+                        work.push(Work::Enter(t1));
+                    },
+                }
+            },
+            Work::ExitAbstraction(binder) => {
+                binders.pop();
+                let body = results.pop().expect("abstraction has a body");
+                results.push(T::abstraction(binder.clone(), body));
+            },
+            Work::ExitApplication => {
+                let t2 = results.pop().expect("application has an argument");
+                let t1 = results.pop().expect("application has a function");
+                results.push(T::application(t1, t2));
+            },
+        }
+    }
+    results.pop().expect("term has a result")
+}
+
+/// Returns true if the terms have the same structure, binders and variable names
+pub fn equal<T: Notation>(t1: &T, t2: &T) -> bool
+    where T::Binder: PartialEq
+{
+    let mut work = vec![(t1, t2)];
+    while let Some((t1, t2)) = work.pop() {
+        match (t1.view(), t2.view()) {
+            (Node::Variable(x1), Node::Variable(x2)) if x1 == x2 => {},
+            (Node::Abstraction(b1, body1), Node::Abstraction(b2, body2)) if b1 == b2 => {
+                work.push((body1, body2));
+            },
+            (Node::Application(f1, a1), Node::Application(f2, a2)) => {
+                work.push((a1, a2));
+                work.push((f1, f2));
+            },
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Renames the binder of an abstraction, if needed, so that it doesn't capture any of the given
+/// free variables, as `Notation::avoid_capture` does, adding any substituting the renaming does to
+/// the counts
+pub(crate) type AvoidCapture<'a, T> = dyn Fn(&<T as Notation>::Binder, &T, &HashSet<<T as Notation>::VariableName>, &mut Counts) -> Option<(<T as Notation>::Binder, T)> + 'a;
+
+/// Replaces the free occurrences of `var` in the term with `substitution`, without capturing any
+/// of the free variables of `substitution`
+pub fn substitute<T: Notation>(term: &T, var: &T::VariableName, substitution: &T) -> T {
+    substitute_with(term, var, substitution, &|binder, body, free, _| T::avoid_capture(binder, body, free), &mut Counts::default())
+}
+
+/// Replaces the free occurrences of `var` in the term with `substitution`, renaming binders that
+/// would capture its free variables with `avoid_capture`, and adding the variables replaced and
+/// binders renamed to `counts`
+pub(crate) fn substitute_with<T: Notation>(term: &T, var: &T::VariableName, substitution: &T, avoid_capture: &AvoidCapture<T>, counts: &mut Counts) -> T {
+    let free = free_variables(substitution);
+    Substitute { var, substitution, free: &free, avoid_capture }.call(term, Vec::new(), counts)
+}
+
+struct Substitute<'s, T: Notation> {
+    var: &'s T::VariableName,
+    substitution: &'s T,
+    free: &'s HashSet<T::VariableName>,
+    avoid_capture: &'s AvoidCapture<'s, T>,
+}
+
+impl<'s, T: Notation> Substitute<'s, T> {
+    fn call(&self, term: &T, binders: Vec<T::Binder>, counts: &mut Counts) -> T {
+        let containing = self.subterms_with_free(term, binders.clone());
+        rebuild_under(term, binders, &mut |term, binders| {
+            if !containing.contains(&(term as *const T)) {
+                return Some(term.clone());
+            }
+            match term.view() {
+                Node::Variable(_) => {
+                    counts.substitutions += 1;
+                    Some(self.substitution.lift(binders.len()))
+                },
+                Node::Abstraction(binder, body) => {
+                    // Only a renamed body is substituted into separately, so this recurses once
+                    // per renaming rather than once per level of nesting
+                    (self.avoid_capture)(binder, body, self.free, counts).map(|(binder, body)| {
+                        counts.renamings += 1;
+                        let mut inner = binders.to_vec();
+                        inner.push(binder.clone());
+                        T::abstraction(binder, self.call(&body, inner, counts))
+                    })
+                },
+                Node::Application(_, _) => None,
+            }
+        })
+    }
+
+    /// Returns the addresses of the subterms of `term`, which occurs under `binders`, in which
+    /// `var` occurs free, so that substitution can tell which subterms it leaves unchanged without
+    /// searching each one
+    fn subterms_with_free(&self, term: &T, mut binders: Vec<T::Binder>) -> HashSet<*const T> {
+        enum Work<'t, T: Notation> {
+            Enter(&'t T),
+            Exit(&'t T),
+        }
+
+        let mut result = HashSet::new();
+        let mut work = vec![Work::Enter(term)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(term) => {
+                    match term.view() {
+                        Node::Variable(name) => {
+                            if T::free_name(name, &binders).as_ref() == Some(self.var) {
+                                result.insert(term as *const T);
+                            }
+                        },
+                        Node::Abstraction(binder, body) => {
+                            binders.push(binder.clone());
+                            work.push(Work::Exit(term));
+                            work.push(Work::Enter(body));
+                        },
+                        Node::Application(t1, t2) => {
+                            work.push(Work::Exit(term));
+                            work.push(Work::Enter(t2));
+                            work.push(Work::Enter(t1));
+                        },
+                    }
+                },
+                Work::Exit(term) => {
+                    let contains = match term.view() {
+                        Node::Abstraction(_, body) => {
+                            binders.pop();
+                            result.contains(&(body as *const T))
+                        },
+                        Node::Application(t1, t2) => result.contains(&(t1 as *const T)) || result.contains(&(t2 as *const T)),
+                        Node::Variable(_) => unreachable!("variables are never exited"),
+                    };
+                    if contains {
+                        result.insert(term as *const T);
+                    }
+                },
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::de_bruijn::{l, a};
+    use crate::notation::{DeBruijn, Named};
+    use crate::parser::{parse_de_bruijn, parse_one};
+
+    #[test]
+    fn test_named_free_variables() {
+        let expected: HashSet<String> = vec!["y".to_string(), "z".to_string()].into_iter().collect();
+        assert_eq!(expected, free_variables(&parse_one(r"\x.x y (\y.y z)").unwrap()));
+    }
+
+    #[test]
+    fn test_de_bruijn_free_variables_are_relative_to_top_level() {
+        let expected: HashSet<u32> = vec![0, 2].into_iter().collect();
+        assert_eq!(expected, free_variables(&parse_de_bruijn(r"\.1 3 (\.0 1)").unwrap()));
+    }
+
+    #[test]
+    fn test_named_substitution_renames_capturing_binder() {
+        let term = substitute(&parse_one(r"\x.x y").unwrap(), &"y".to_string(), &parse_one("x").unwrap());
+        assert!(parse_one(r"\a.a x").unwrap().is_alpha_equivalent(&term));
+    }
+
+    #[test]
+    fn test_named_substitution_stops_at_shadowing_binder() {
+        let term = parse_one(r"\y.y").unwrap();
+        assert_eq!(term, substitute(&term, &"y".to_string(), &parse_one("x").unwrap()));
+    }
+
+    #[test]
+    fn test_de_bruijn_substitution_lifts_substitute_under_binders() {
+        assert_eq!(
+            l(a(2u32, l(3u32))),
+            substitute(&l(a(1u32, l(2u32))), &0, &DeBruijn::Variable(1))
+        );
+    }
+
+    #[test]
+    fn test_size_and_depth() {
+        let named: Named = parse_one(r"\x.x (y z)").unwrap();
+        let de_bruijn: DeBruijn = parse_de_bruijn(r"\.0 (1 2)").unwrap();
+        assert_eq!((6, 3), (named.size(), named.depth()));
+        assert_eq!((6, 3), (de_bruijn.size(), de_bruijn.depth()));
+    }
+
+    #[test]
+    fn test_rebuild_replaces_subterms_without_visiting_their_children() {
+        let term: Named = parse_one(r"\x.f (g x) x").unwrap();
+        let mut visited = Vec::new();
+        let result = rebuild(&term, &mut |t: &Named, binders: &[String]| match t {
+            Named::Application(t1, _) if **t1 == Named::Variable("g".to_string()) => Some(Named::Variable("h".to_string())),
+            Named::Variable(name) => {
+                visited.push((name.clone(), binders.len()));
+                None
+            },
+            _ => None,
+        });
+        assert_eq!(parse_one(r"\x.f h x").unwrap(), result);
+        assert_eq!(vec![("f".to_string(), 1), ("x".to_string(), 1)], visited);
+    }
+
+    #[test]
+    fn test_equal_compares_binder_names() {
+        let t1: Named = parse_one(r"\x.x").unwrap();
+        assert!(equal(&t1, &parse_one(r"\x.x").unwrap()));
+        assert!(!equal(&t1, &parse_one(r"\y.y").unwrap()));
+    }
+}
@@ -0,0 +1,427 @@
+//! Locally nameless terms
+//!
+//! Bound variables are De Bruijn indices, so alpha-equivalent terms are equal, while free
+//! variables keep their names, so converting from a named term needs no naming context. Moving
+//! under a binder means opening its body, replacing the bound index with a fresh free name, and
+//! the result is turned back into a body by closing over that name.
+use std::{
+    collections::HashSet,
+    fmt,
+    mem,
+};
+
+use crate::substitution::{FreshNames, Primes};
+
+use super::conversion::{restore_names, ConversionError};
+use super::{generic, DeBruijn, Named, Node, Notation};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Variable {
+    Bound(u32),
+    Free(String),
+}
+
+#[derive(Debug)]
+pub enum Term {
+    Variable(Variable),
+    Abstraction(Box<Term>),
+    Application(Box<Term>, Box<Term>),
+}
+
+impl Term {
+    /// Returns the body of an abstraction with its bound variable replaced by `arg`
+    pub fn open(&self, arg: &Term) -> Term {
+        generic::rebuild(self, &mut |term, binders| match term {
+            Term::Variable(Variable::Bound(index)) if *index == binders.len() as u32 => Some(arg.clone()),
+            _ => None,
+        })
+    }
+
+    /// Returns the body of an abstraction that binds the free variable `name`, the inverse of
+    /// opening with that name
+    pub fn close(&self, name: &str) -> Term {
+        generic::rebuild(self, &mut |term, binders| match term {
+            Term::Variable(Variable::Free(free)) if free == name => Some(Term::Variable(Variable::Bound(binders.len() as u32))),
+            _ => None,
+        })
+    }
+
+    /// Returns true if every bound variable refers to an enclosing abstraction
+    pub fn is_locally_closed(&self) -> bool {
+        let mut work = vec![(self, 0)];
+        while let Some((term, depth)) = work.pop() {
+            match term {
+                Term::Variable(Variable::Bound(index)) if *index >= depth => return false,
+                Term::Variable(_) => {},
+                Term::Abstraction(body) => work.push((body, depth + 1)),
+                Term::Application(t1, t2) => {
+                    work.push((t2, depth));
+                    work.push((t1, depth));
+                },
+            }
+        }
+        true
+    }
+
+    /// Returns the names of the free variables in this term
+    pub fn free_names(&self) -> HashSet<String> {
+        generic::free_variables(self)
+            .into_iter()
+            .filter_map(|variable| match variable {
+                Variable::Free(name) => Some(name),
+                Variable::Bound(_) => None,
+            })
+            .collect()
+    }
+
+    /// Opens the body of an abstraction with a free variable that doesn't occur in it, returning
+    /// the variable's name along with the opened body
+    pub fn open_fresh(&self) -> (String, Term) {
+        let name = FreshNames::new(&Primes, self.free_names()).fresh("x");
+        let body = self.open(&Term::Variable(Variable::Free(name.clone())));
+        (name, body)
+    }
+
+    /// Converts this term into a De Bruijn term, where the last name in `free_variables` has the
+    /// lowest index at the top level. Fails if a free name isn't in `free_variables`.
+    pub fn to_de_bruijn(&self, free_variables: &[String]) -> Result<DeBruijn, ConversionError> {
+        enum Work<'t> {
+            Enter(&'t Term, u32),
+            ExitAbstraction,
+            ExitApplication,
+        }
+
+        let mut results = Vec::new();
+        let mut work = vec![Work::Enter(self, 0)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(Term::Variable(Variable::Bound(index)), _) => {
+                    results.push(DeBruijn::Variable(*index));
+                },
+                Work::Enter(Term::Variable(Variable::Free(name)), depth) => {
+                    let position = free_variables
+                        .iter()
+                        .rev()
+                        .position(|n| n == name)
+                        .ok_or_else(|| ConversionError::UnboundVariable(name.clone()))?;
+                    results.push(DeBruijn::Variable(depth + position as u32));
+                },
+                Work::Enter(Term::Abstraction(body), depth) => {
+                    work.push(Work::ExitAbstraction);
+                    work.push(Work::Enter(body, depth + 1));
+                },
+                Work::Enter(Term::Application(t1, t2), depth) => {
+                    work.push(Work::ExitApplication);
+                    work.push(Work::Enter(t2, depth));
+                    work.push(Work::Enter(t1, depth));
+                },
+                Work::ExitAbstraction => {
+                    let body = results.pop().expect("abstraction has a body");
+                    results.push(DeBruijn::Abstraction(box body));
+                },
+                Work::ExitApplication => {
+                    let t2 = results.pop().expect("application has an argument");
+                    let t1 = results.pop().expect("application has a function");
+                    results.push(DeBruijn::Application(box t1, box t2));
+                },
+            }
+        }
+        Ok(results.pop().expect("term has a result"))
+    }
+
+    /// Converts a De Bruijn term into a locally nameless term, naming its free variables from
+    /// `free_variables`, the inverse of `to_de_bruijn`. Fails if a free index is past the end of
+    /// `free_variables`.
+    pub fn from_de_bruijn(term: &DeBruijn, free_variables: &[String]) -> Result<Term, ConversionError> {
+        enum Work<'t> {
+            Enter(&'t DeBruijn, u32),
+            ExitAbstraction,
+            ExitApplication,
+        }
+
+        let mut results = Vec::new();
+        let mut work = vec![Work::Enter(term, 0)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(DeBruijn::Variable(index), depth) if *index < depth => {
+                    results.push(Term::Variable(Variable::Bound(*index)));
+                },
+                Work::Enter(DeBruijn::Variable(index), depth) => {
+                    let name = free_variables
+                        .iter()
+                        .rev()
+                        .nth((index - depth) as usize)
+                        .ok_or(ConversionError::UnboundIndex(index - depth))?;
+                    results.push(Term::Variable(Variable::Free(name.clone())));
+                },
+                Work::Enter(DeBruijn::Abstraction(body), depth) => {
+                    work.push(Work::ExitAbstraction);
+                    work.push(Work::Enter(body, depth + 1));
+                },
+                Work::Enter(DeBruijn::Application(t1, t2), depth) => {
+                    work.push(Work::ExitApplication);
+                    work.push(Work::Enter(t2, depth));
+                    work.push(Work::Enter(t1, depth));
+                },
+                Work::ExitAbstraction => {
+                    let body = results.pop().expect("abstraction has a body");
+                    results.push(Term::Abstraction(box body));
+                },
+                Work::ExitApplication => {
+                    let t2 = results.pop().expect("application has an argument");
+                    let t1 = results.pop().expect("application has a function");
+                    results.push(Term::Application(box t1, box t2));
+                },
+            }
+        }
+        Ok(results.pop().expect("term has a result"))
+    }
+
+    /// Returns the free names of this term in sorted order, so that conversions that need a
+    /// naming context give the same result on every run
+    fn sorted_free_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.free_names().into_iter().collect();
+        names.sort();
+        names
+    }
+}
+
+impl From<&Named> for Term {
+    fn from(named: &Named) -> Term {
+        enum Work<'n> {
+            Enter(&'n Named),
+            ExitAbstraction,
+            ExitApplication,
+        }
+
+        let mut binders: Vec<&str> = Vec::new();
+        let mut results = Vec::new();
+        let mut work = vec![Work::Enter(named)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(Named::Variable(name)) => {
+                    results.push(match binders.iter().rev().position(|binder| binder == name) {
+                        Some(index) => Term::Variable(Variable::Bound(index as u32)),
+                        None => Term::Variable(Variable::Free(name.clone())),
+                    });
+                },
+                Work::Enter(Named::Abstraction(name, body)) => {
+                    binders.push(name);
+                    work.push(Work::ExitAbstraction);
+                    work.push(Work::Enter(body));
+                },
+                Work::Enter(Named::Application(t1, t2)) => {
+                    work.push(Work::ExitApplication);
+                    work.push(Work::Enter(t2));
+                    work.push(Work::Enter(t1));
+                },
+                Work::ExitAbstraction => {
+                    binders.pop();
+                    let body = results.pop().expect("abstraction has a body");
+                    results.push(Term::Abstraction(box body));
+                },
+                Work::ExitApplication => {
+                    let t2 = results.pop().expect("application has an argument");
+                    let t1 = results.pop().expect("application has a function");
+                    results.push(Term::Application(box t1, box t2));
+                },
+            }
+        }
+        results.pop().expect("term has a result")
+    }
+}
+
+impl From<&Term> for Named {
+    /// Bound variables are given the first of `a`, `b`, ... that doesn't conflict with another
+    /// name in scope
+    fn from(term: &Term) -> Named {
+        let free_variables = term.sorted_free_names();
+        let de_bruijn = term.to_de_bruijn(&free_variables).expect("every free name is in the naming context");
+        restore_names(&de_bruijn, &free_variables).expect("every free index is in the naming context")
+    }
+}
+
+impl From<&Term> for DeBruijn {
+    /// Free variables are numbered in sorted order of their names
+    fn from(term: &Term) -> DeBruijn {
+        term.to_de_bruijn(&term.sorted_free_names()).expect("every free name is in the naming context")
+    }
+}
+
+/// Drops the term one node at a time, as named terms are, so that deep terms can be dropped
+impl Drop for Term {
+    fn drop(&mut self) {
+        let mut work = Vec::new();
+        take_subterms(self, &mut work);
+        while let Some(mut term) = work.pop() {
+            take_subterms(&mut term, &mut work);
+        }
+    }
+}
+
+/// Moves the compound subterms of `term` onto `work`, leaving variables behind
+fn take_subterms(term: &mut Term, work: &mut Vec<Term>) {
+    let mut take = |subterm: &mut Box<Term>| {
+        if let Term::Variable(_) = **subterm {
+            return;
+        }
+        work.push(mem::replace(&mut **subterm, Term::Variable(Variable::Bound(0))));
+    };
+    match term {
+        Term::Variable(_) => {},
+        Term::Abstraction(body) => {
+            take(body);
+        },
+        Term::Application(t1, t2) => {
+            take(t1);
+            take(t2);
+        },
+    }
+}
+
+impl Clone for Term {
+    fn clone(&self) -> Term {
+        generic::rebuild(self, &mut |_, _| None)
+    }
+}
+
+impl PartialEq for Term {
+    fn eq(&self, other: &Term) -> bool {
+        generic::equal(self, other)
+    }
+}
+
+impl Notation for Term {
+    type VariableName = Variable;
+    type Binder = ();
+
+    fn application(func: Self, arg: Self) -> Self {
+        Term::Application(box func, box arg)
+    }
+
+    fn abstraction(_binder: (), body: Self) -> Self {
+        Term::Abstraction(box body)
+    }
+
+    fn variable(name: Variable) -> Self {
+        Term::Variable(name)
+    }
+
+    fn view(&self) -> Node<'_, Self, &Self> {
+        match self {
+            Term::Variable(variable) => Node::Variable(variable),
+            Term::Abstraction(body) => Node::Abstraction(&(), body),
+            Term::Application(t1, t2) => Node::Application(t1, t2),
+        }
+    }
+
+    fn function_mut(&mut self) -> Option<&mut Self> {
+        match self {
+            Term::Application(t1, _) => Some(t1),
+            _ => None,
+        }
+    }
+
+    fn free_name(variable: &Variable, binders: &[()]) -> Option<Variable> {
+        match variable {
+            Variable::Bound(index) => index.checked_sub(binders.len() as u32).map(Variable::Bound),
+            Variable::Free(_) => Some(variable.clone()),
+        }
+    }
+
+    /// Locally closed terms have no dangling indices, so are unchanged under binders
+    fn lift(&self, _count: usize) -> Self {
+        self.clone()
+    }
+
+    /// Free variables are names and bound variables are indices, so they can't be captured
+    fn avoid_capture(_binder: &(), _body: &Self, _free: &HashSet<Variable>) -> Option<((), Self)> {
+        None
+    }
+
+    fn instantiate(_binder: &(), body: &Self, arg: &Self) -> Self {
+        body.open(arg)
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Named::from(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::testing::Random;
+    use crate::parser::parse_one;
+
+    fn bound(index: u32) -> Term {
+        Term::Variable(Variable::Bound(index))
+    }
+
+    fn free(name: &str) -> Term {
+        Term::Variable(Variable::Free(name.to_string()))
+    }
+
+    #[test]
+    fn test_converts_named_term_keeping_free_names() {
+        assert_eq!(
+            Term::Abstraction(box Term::Application(box bound(0), box free("y"))),
+            Term::from(&parse_one(r"\x.x y").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_opens_body_with_term() {
+        let body = Term::Application(box bound(0), box Term::Abstraction(box Term::Application(box bound(1), box bound(0))));
+        assert_eq!(
+            Term::Application(box free("z"), box Term::Abstraction(box Term::Application(box free("z"), box bound(0)))),
+            body.open(&free("z"))
+        );
+    }
+
+    #[test]
+    fn test_close_is_inverse_of_open() {
+        let body = match Term::from(&parse_one(r"\x.\y.x y").unwrap()) {
+            Term::Abstraction(ref body) => (**body).clone(),
+            term => panic!("expected an abstraction, found {:?}", term),
+        };
+        assert!(!body.is_locally_closed());
+        assert_eq!(body, body.open(&free("x")).close("x"));
+    }
+
+    #[test]
+    fn test_open_fresh_avoids_free_names() {
+        let (name, _) = Term::from(&parse_one(r"x' x").unwrap()).open_fresh();
+        assert_eq!("x''", name);
+    }
+
+    #[test]
+    fn test_alpha_equivalent_terms_are_equal() {
+        assert_eq!(Term::from(&parse_one(r"\x.x z").unwrap()), Term::from(&parse_one(r"\y.y z").unwrap()));
+    }
+
+    #[test]
+    fn test_converts_to_de_bruijn_with_sorted_free_variables() {
+        assert_eq!(
+            crate::parser::parse_de_bruijn(r"\.0 2 1").unwrap(),
+            DeBruijn::from(&Term::from(&parse_one(r"\x.x a b").unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_conversions_round_trip() {
+        let mut random = Random::new(0x10ca_11e5_0000_0044);
+        let free_variables = vec!["a".to_string(), "b".to_string()];
+        for _ in 0..500 {
+            let named = random.term(6, &mut vec!["a", "b"]);
+            let term = Term::from(&named);
+            assert!(term.is_locally_closed());
+            assert!(named.is_alpha_equivalent(&Named::from(&term)));
+
+            let de_bruijn = term.to_de_bruijn(&free_variables).unwrap();
+            assert_eq!(term, Term::from_de_bruijn(&de_bruijn, &free_variables).unwrap());
+        }
+    }
+}
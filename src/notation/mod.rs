@@ -1,22 +1,133 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
 pub mod conversion;
 pub mod de_bruijn;
+pub mod generic;
+pub mod locally_nameless;
 pub mod named;
+pub mod pretty;
+pub mod render;
+pub mod shared;
 
 pub use de_bruijn::Term as DeBruijn;
+pub use locally_nameless::Term as LocallyNameless;
 pub use named::Term as Named;
 
+/// A term representation.
+///
+/// Besides constructors, notations provide a view of a term's outermost node and a few hooks
+/// describing how they bind variables. Functions written against this trait, such as the ones in
+/// the `generic` module, work for every notation.
 pub trait Notation
-    : std::fmt::Display
+    : std::fmt::Display + Clone + Sized
 {
     /// Type used to represent variable names
-    type VariableName;
+    type VariableName: Clone + Eq + Hash;
+
+    /// Type of the information an abstraction carries about its bound variable
+    type Binder: Clone;
 
     /// Returns a term representing the application of `arg` to `func`
     fn application(func: Self, arg: Self) -> Self;
 
-    /// Returns an abstraction term with the given body and bound variable name
-    fn abstraction(bound_var_name: Self::VariableName, body: Self) -> Self;
+    /// Returns an abstraction term with the given body and binder
+    fn abstraction(binder: Self::Binder, body: Self) -> Self;
 
     /// Returns a variable term with the given name
     fn variable(name: Self::VariableName) -> Self;
+
+    /// Returns the outermost node of the term, with its immediate subterms
+    fn view(&self) -> Node<'_, Self, &Self>;
+
+    /// Returns the function of an application for changing in place, or `None` if the term isn't
+    /// an application
+    fn function_mut(&mut self) -> Option<&mut Self>;
+
+    /// Returns the name that `variable`, occurring under `binders` (innermost last), has outside of
+    /// them, or `None` if one of the binders binds it
+    fn free_name(variable: &Self::VariableName, binders: &[Self::Binder]) -> Option<Self::VariableName>;
+
+    /// Returns this term as it should appear when placed under `count` more binders
+    fn lift(&self, count: usize) -> Self;
+
+    /// Renames the binder of an abstraction, if needed, so that it doesn't capture any of the given
+    /// free variables when they're substituted into `body`. Returns `None` if no renaming is needed.
+    fn avoid_capture(binder: &Self::Binder, body: &Self, free: &HashSet<Self::VariableName>) -> Option<(Self::Binder, Self)>;
+
+    /// Returns the result of contracting the redex `(λbinder.body) arg`
+    fn instantiate(binder: &Self::Binder, body: &Self, arg: &Self) -> Self;
+
+    /// Combines the results for each node of the term, from the leaves up. Nodes are visited
+    /// left to right, using an explicit stack rather than recursion.
+    fn fold<'t, R>(&'t self, f: &mut dyn FnMut(Node<'t, Self, R>) -> R) -> R {
+        enum Work<'t, T> {
+            Enter(&'t T),
+            Exit(&'t T),
+        }
+
+        let mut results = Vec::new();
+        let mut work = vec![Work::Enter(self)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(term) => {
+                    match term.view() {
+                        Node::Variable(name) => {
+                            results.push(f(Node::Variable(name)));
+                        },
+                        Node::Abstraction(_, body) => {
+                            work.push(Work::Exit(term));
+                            work.push(Work::Enter(body));
+                        },
+                        Node::Application(t1, t2) => {
+                            work.push(Work::Exit(term));
+                            work.push(Work::Enter(t2));
+                            work.push(Work::Enter(t1));
+                        },
+                    }
+                },
+                Work::Exit(term) => {
+                    let node = match term.view() {
+                        Node::Abstraction(binder, _) => {
+                            let body = results.pop().expect("abstraction has a body");
+                            Node::Abstraction(binder, body)
+                        },
+                        Node::Application(_, _) => {
+                            let t2 = results.pop().expect("application has an argument");
+                            let t1 = results.pop().expect("application has a function");
+                            Node::Application(t1, t2)
+                        },
+                        Node::Variable(_) => unreachable!("variables are never exited"),
+                    };
+                    results.push(f(node));
+                },
+            }
+        }
+        results.pop().expect("term has a result")
+    }
+
+    /// Returns the number of nodes in the term
+    fn size(&self) -> usize {
+        self.fold(&mut |node| match node {
+            Node::Variable(_) => 1,
+            Node::Abstraction(_, body) => 1 + body,
+            Node::Application(t1, t2) => 1 + t1 + t2,
+        })
+    }
+
+    /// Returns the length of the longest path from the root of the term to a variable
+    fn depth(&self) -> usize {
+        self.fold(&mut |node| match node {
+            Node::Variable(_) => 0,
+            Node::Abstraction(_, body) => 1 + body,
+            Node::Application(t1, t2) => 1 + t1.max(t2),
+        })
+    }
+}
+
+/// A single node of a term, with `R` in place of its subterms
+pub enum Node<'t, T: Notation, R> {
+    Variable(&'t T::VariableName),
+    Abstraction(&'t T::Binder, R),
+    Application(R, R),
 }
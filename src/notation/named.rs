@@ -1,23 +1,56 @@
 use std::{
+    collections::HashSet,
     fmt,
+    mem,
 };
 
-use super::Notation;
+use crate::substitution::{self, Counts, Primes, Substitutable};
+
+use super::{generic, Node, Notation};
+use super::pretty::Pretty;
 
-#[derive(Clone, PartialEq)]
 pub enum Term {
     Variable(String),
     Abstraction(String, Box<Term>),
     Application(Box<Term>, Box<Term>),
 }
 
+/// The parts of a term, taken out of it by value. Terms implement `Drop`, so a term can't be taken
+/// apart by moving out of its fields.
+pub enum Parts {
+    Variable(String),
+    Abstraction(String, Term),
+    Application(Term, Term),
+}
+
 impl Term {
+    /// Takes the term apart into its name and immediate subterms
+    pub fn into_parts(mut self) -> Parts {
+        match &mut self {
+            Term::Variable(name) => Parts::Variable(mem::take(name)),
+            Term::Abstraction(name, body) => Parts::Abstraction(mem::take(name), body.take()),
+            Term::Application(t1, t2) => Parts::Application(t1.take(), t2.take()),
+        }
+    }
+
+    /// Moves the term out, leaving a variable with an empty name in its place
+    pub fn take(&mut self) -> Term {
+        mem::replace(self, Term::Variable(String::new()))
+    }
+
     pub fn is_redex(&self) -> bool {
-        match self {
-            Term::Application(box Term::Abstraction(_, _), _) => true,
-            Term::Application(t1, t2) => t1.is_redex() || t2.is_redex(),
-            _ => false,
+        let mut work = vec![self];
+        while let Some(term) = work.pop() {
+            match term {
+                Term::Application(box Term::Abstraction(_, _), _) => return true,
+                Term::Application(t1, t2) => {
+                    work.push(t2);
+                    work.push(t1);
+                },
+                _ => {},
+            }
         }
+        false
     }
 
     pub fn is_value(&self) -> bool {
@@ -26,10 +59,187 @@ impl Term {
             _ => false,
         }
     }
+
+    /// Returns true if this term contains no redexes, including inside abstractions
+    pub fn is_normal_form(&self) -> bool {
+        let mut work = vec![self];
+        while let Some(term) = work.pop() {
+            match term {
+                Term::Variable(_) => {},
+                Term::Abstraction(_, body) => work.push(body),
+                Term::Application(box Term::Abstraction(_, _), _) => return false,
+                Term::Application(t1, t2) => {
+                    work.push(t2);
+                    work.push(t1);
+                },
+            }
+        }
+        true
+    }
+
+    /// Returns true if this term has the form `λx1...λxn.y t1 ... tm`, so that no reduction of
+    /// it can change its head variable
+    pub fn is_head_normal_form(&self) -> bool {
+        let mut term = self;
+        while let Term::Abstraction(_, body) = term {
+            term = body;
+        }
+        term.has_variable_head()
+    }
+
+    /// Returns true if this term is an abstraction or has the form `y t1 ... tm`
+    pub fn is_whnf(&self) -> bool {
+        match self {
+            Term::Abstraction(_, _) => true,
+            _ => self.has_variable_head(),
+        }
+    }
+
+    /// Returns true if this term is a variable applied to zero or more arguments
+    pub(crate) fn has_variable_head(&self) -> bool {
+        let mut term = self;
+        while let Term::Application(t1, _) = term {
+            term = t1;
+        }
+        matches!(term, Term::Variable(_))
+    }
+
+    /// Returns every variable name that occurs in this term, whether free or bound
+    pub fn names(&self) -> HashSet<String> {
+        let mut names = HashSet::new();
+        let mut work = vec![self];
+        while let Some(term) = work.pop() {
+            match term {
+                Term::Variable(name) => {
+                    names.insert(name.clone());
+                },
+                Term::Abstraction(name, body) => {
+                    names.insert(name.clone());
+                    work.push(body);
+                },
+                Term::Application(t1, t2) => {
+                    work.push(t2);
+                    work.push(t1);
+                },
+            }
+        }
+        names
+    }
+
+    /// Returns true if the variable `name` occurs free in this term. This stops at the first
+    /// occurrence, rather than collecting every free variable.
+    pub fn occurs_free(&self, name: &str) -> bool {
+        let mut work = vec![self];
+        while let Some(term) = work.pop() {
+            match term {
+                Term::Variable(x) if x == name => return true,
+                Term::Variable(_) => {},
+                Term::Abstraction(x, _) if x == name => {},
+                Term::Abstraction(_, body) => work.push(body),
+                Term::Application(t1, t2) => {
+                    work.push(t2);
+                    work.push(t1);
+                },
+            }
+        }
+        false
+    }
+
+    /// Returns true if this term is equal to `other` up to renaming of bound variables
+    pub fn is_alpha_equivalent(&self, other: &Term) -> bool {
+        enum Work<'t> {
+            Compare(&'t Term, &'t Term),
+            ExitAbstraction,
+        }
+
+        let mut names1: Vec<&str> = Vec::new();
+        let mut names2: Vec<&str> = Vec::new();
+        let mut work = vec![Work::Compare(self, other)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Compare(Term::Variable(x1), Term::Variable(x2)) => {
+                    let p1 = names1.iter().rposition(|n| n == x1);
+                    let p2 = names2.iter().rposition(|n| n == x2);
+                    let equivalent = match (p1, p2) {
+                        (None, None) => x1 == x2,
+                        (p1, p2) => p1 == p2,
+                    };
+                    if !equivalent {
+                        return false;
+                    }
+                },
+                Work::Compare(Term::Abstraction(x1, b1), Term::Abstraction(x2, b2)) => {
+                    names1.push(x1);
+                    names2.push(x2);
+                    work.push(Work::ExitAbstraction);
+                    work.push(Work::Compare(b1, b2));
+                },
+                Work::Compare(Term::Application(f1, a1), Term::Application(f2, a2)) => {
+                    work.push(Work::Compare(a1, a2));
+                    work.push(Work::Compare(f1, f2));
+                },
+                Work::Compare(_, _) => {
+                    return false;
+                },
+                Work::ExitAbstraction => {
+                    names1.pop();
+                    names2.pop();
+                },
+            }
+        }
+        true
+    }
+}
+
+/// Drops the term one node at a time. Dropping the boxed subterms would otherwise recurse once for
+/// each level of nesting, which overflows the stack for terms hundreds of thousands of levels deep.
+impl Drop for Term {
+    fn drop(&mut self) {
+        let mut work = Vec::new();
+        take_subterms(self, &mut work);
+        while let Some(mut term) = work.pop() {
+            take_subterms(&mut term, &mut work);
+        }
+    }
+}
+
+/// Moves the subterms of `term` that have subterms of their own onto `work`, leaving variables in
+/// their place, so that dropping `term` doesn't recurse
+fn take_subterms(term: &mut Term, work: &mut Vec<Term>) {
+    let mut take = |subterm: &mut Box<Term>| {
+        if let Term::Variable(_) = **subterm {
+            return;
+        }
+        work.push(mem::replace(&mut **subterm, Term::Variable(String::new())));
+    };
+    match term {
+        Term::Variable(_) => {},
+        Term::Abstraction(_, body) => {
+            take(body);
+        },
+        Term::Application(t1, t2) => {
+            take(t1);
+            take(t2);
+        },
+    }
+}
+
+/// Copies the term without recursing, so that deep terms can be cloned
+impl Clone for Term {
+    fn clone(&self) -> Term {
+        generic::rebuild(self, &mut |_, _| None)
+    }
+}
+
+impl PartialEq for Term {
+    fn eq(&self, other: &Term) -> bool {
+        generic::equal(self, other)
+    }
 }
 
 impl Notation for Term {
     type VariableName = String;
+    type Binder = String;
 
     /// Returns a term representing the application of `arg` to `func`
     fn application(func: Self, arg: Self) -> Self {
@@ -37,7 +247,7 @@ impl Notation for Term {
     }
 
     /// Returns an abstraction term with the given body and bound variable name
-    fn abstraction(bound_var_name: Self::VariableName, body: Self) -> Self {
+    fn abstraction(bound_var_name: Self::Binder, body: Self) -> Self {
         Term::Abstraction(bound_var_name, box body)
     }
 
@@ -45,27 +255,61 @@ impl Notation for Term {
     fn variable(name: Self::VariableName) -> Self {
         Term::Variable(name)
     }
+
+    fn view(&self) -> Node<'_, Self, &Self> {
+        match self {
+            Term::Variable(name) => Node::Variable(name),
+            Term::Abstraction(name, body) => Node::Abstraction(name, body),
+            Term::Application(t1, t2) => Node::Application(t1, t2),
+        }
+    }
+
+    fn function_mut(&mut self) -> Option<&mut Self> {
+        match self {
+            Term::Application(t1, _) => Some(t1),
+            _ => None,
+        }
+    }
+
+    fn free_name(variable: &String, binders: &[String]) -> Option<String> {
+        if binders.contains(variable) {
+            None
+        } else {
+            Some(variable.clone())
+        }
+    }
+
+    /// Named terms refer to variables by name, so don't change under binders
+    fn lift(&self, _count: usize) -> Self {
+        self.clone()
+    }
+
+    fn avoid_capture(name: &String, body: &Self, free: &HashSet<String>) -> Option<(String, Self)> {
+        substitution::rename(name, body, free, &Primes, &mut Counts::default())
+    }
+
+    fn instantiate(name: &String, body: &Self, arg: &Self) -> Self {
+        body.substitute(name.as_str(), arg)
+    }
 }
 
 impl fmt::Display for Term {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Term::Variable(s) => write!(f, "{}", s),
-            Term::Abstraction(s, t) => write!(f, "λ{}.{}", s, t),
-            Term::Application(box Term::Variable(t1), box Term::Variable(t2)) => write!(f, "{} {}", t1, t2),
-            Term::Application(box Term::Variable(t1), t2) => write!(f, "{} ({})", t1, t2),
-            Term::Application(t1, box Term::Variable(t2)) => write!(f, "({}) {}", t1, t2),
-            Term::Application(t1, t2) => write!(f, "({}) ({})", t1, t2),
-        }
+        write!(f, "{}", Pretty::new().print(self))
     }
 }
 
+/// Prints the term in the parser's syntax, or with `{:#?}`, as calls to the `l` and `a` helpers
 impl fmt::Debug for Term {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return write!(f, "Named({})", Pretty::new().ascii().print(self));
+        }
+
         match self {
-            Term::Variable(s) => write!(f, "{:?}", s),
-            Term::Abstraction(s, t) => write!(f, "l({:?}, {:?})", s, t),
-            Term::Application(t1, t2) => write!(f, "a({:?}, {:?})", t1, t2),
+            Term::Variable(s) => write!(f, "{:#?}", s),
+            Term::Abstraction(s, t) => write!(f, "l({:#?}, {:#?})", s, t),
+            Term::Application(t1, t2) => write!(f, "a({:#?}, {:#?})", t1, t2),
         }
     }
 }
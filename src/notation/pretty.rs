@@ -0,0 +1,453 @@
+//! Pretty printing with minimal parentheses
+//!
+//! Application is left associative and an abstraction extends as far right as possible, so
+//! parentheses are only needed around an application in argument position, an abstraction in
+//! function position, and an abstraction that something else follows. Nested abstractions are
+//! collapsed into a single `λx y z.`.
+//!
+//! Terms that don't fit in the line width are broken after the `.` of an abstraction, and between
+//! the arguments of an application, with each continuation line indented. The parser treats
+//! indented lines as continuations, so the output always parses back to the same term.
+//!
+//! By default abstractions are printed with `λ`. The ASCII mode prints `\` instead, which is
+//! easier to type and is exactly the syntax the parser's documentation uses.
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::de_bruijn::Term as DeBruijn;
+use super::named::Term;
+
+const INDENT: usize = 2;
+
+pub struct Pretty {
+    width: Option<usize>,
+    lambda: &'static str,
+}
+
+impl Default for Pretty {
+    fn default() -> Pretty {
+        Pretty::new()
+    }
+}
+
+impl Pretty {
+    /// A printer that never breaks lines
+    pub fn new() -> Pretty {
+        Pretty { width: None, lambda: "λ" }
+    }
+
+    /// A printer that breaks lines longer than `width` characters, where possible
+    pub fn with_width(width: usize) -> Pretty {
+        Pretty { width: Some(width), lambda: "λ" }
+    }
+
+    /// Prints abstractions with `\` instead of `λ`
+    pub fn ascii(self) -> Pretty {
+        Pretty { lambda: "\\", ..self }
+    }
+
+    pub fn print(&self, term: &Term) -> String {
+        let mut out = String::new();
+        match self.width {
+            Some(width) => self.layout(&mut out, term, width),
+            None => self.flat(&mut out, term, true),
+        }
+        out
+    }
+
+    /// Prints a De Bruijn term, such as `λ.λ.1 (λ.0)`, on a single line
+    pub fn print_de_bruijn(&self, term: &DeBruijn) -> String {
+        let mut out = String::new();
+        self.flat_de_bruijn(&mut out, term);
+        out
+    }
+
+    /// Lays out `term`, breaking the lines of subterms that don't fit in `width` columns where they
+    /// start. Like the other printing functions, this keeps a stack of work instead of recursing,
+    /// so that deep terms can be printed.
+    fn layout(&self, out: &mut String, term: &Term, width: usize) {
+        let widths = self.flat_widths(term);
+        let mut work = vec![Task::Layout(term, 0, true)];
+        while let Some(task) = work.pop() {
+            let (term, indent, last) = match task {
+                Task::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                },
+                Task::Break(indent) => {
+                    out.push('\n');
+                    out.push_str(&spaces(indent));
+                    continue;
+                },
+                Task::Layout(term, indent, last) => (term, indent, last),
+            };
+
+            // `last` is true if nothing follows the term before the end of the enclosing
+            // parentheses, so that an abstraction needs none of its own
+            let flat_width = widths[&(term as *const Term)];
+            if indent + if last { flat_width.0 } else { flat_width.1 } <= width {
+                self.flat(out, term, last);
+                continue;
+            }
+
+            match term {
+                Term::Variable(name) => {
+                    out.push_str(name);
+                },
+                Term::Abstraction(_, _) if !last => {
+                    out.push('(');
+                    work.push(Task::Text(")"));
+                    work.push(Task::Layout(term, indent + 1, true));
+                },
+                Term::Abstraction(_, _) => {
+                    let (names, body) = collapse(term);
+                    let inner = indent + INDENT;
+                    write!(out, "{}{}.", self.lambda, names.join(" ")).unwrap();
+                    work.push(Task::Layout(body, inner, true));
+                    work.push(Task::Break(inner));
+                },
+                Term::Application(_, _) => {
+                    let (head, args) = spine(term);
+                    let inner = indent + INDENT;
+                    for (i, arg) in args.iter().enumerate().rev() {
+                        let arg_last = last && i == args.len() - 1;
+                        match arg {
+                            Term::Application(_, _) => {
+                                work.push(Task::Text(")"));
+                                work.push(Task::Layout(arg, inner + 1, true));
+                                work.push(Task::Text("("));
+                            },
+                            _ => {
+                                work.push(Task::Layout(arg, inner, arg_last));
+                            },
+                        }
+                        work.push(Task::Break(inner));
+                    }
+                    match head {
+                        Term::Abstraction(_, _) => {
+                            work.push(Task::Text(")"));
+                            work.push(Task::Layout(head, indent + 1, true));
+                            work.push(Task::Text("("));
+                        },
+                        _ => {
+                            work.push(Task::Layout(head, indent, false));
+                        },
+                    }
+                },
+            }
+        }
+    }
+
+    /// Returns the number of characters each subterm takes up printed on a single line, both when
+    /// it's the last thing before a closing parenthesis and when it isn't
+    fn flat_widths(&self, term: &Term) -> HashMap<*const Term, (usize, usize)> {
+        let lambda = self.lambda.chars().count();
+        let mut widths: HashMap<*const Term, (usize, usize)> = HashMap::new();
+        let mut work = vec![(term, false)];
+        while let Some((term, exiting)) = work.pop() {
+            if !exiting {
+                work.push((term, true));
+                match term {
+                    Term::Variable(_) => {},
+                    Term::Abstraction(_, body) => work.push((body, false)),
+                    Term::Application(t1, t2) => {
+                        work.push((t2, false));
+                        work.push((t1, false));
+                    },
+                }
+                continue;
+            }
+
+            let width = |t: &Term| widths[&(t as *const Term)];
+            let last = match term {
+                Term::Variable(name) => {
+                    name.chars().count()
+                },
+                Term::Abstraction(name, body) => {
+                    match **body {
+                        // Collapsed into `λname rest.`, which is the body with ` name` added
+                        Term::Abstraction(_, _) => width(body).0 + name.chars().count() + 1,
+                        _ => lambda + name.chars().count() + 1 + width(body).0,
+                    }
+                },
+                Term::Application(t1, t2) => {
+                    let function = match **t1 {
+                        Term::Abstraction(_, _) => width(t1).0 + 2,
+                        _ => width(t1).1,
+                    };
+                    let argument = match **t2 {
+                        Term::Application(_, _) => width(t2).0 + 2,
+                        _ => width(t2).0,
+                    };
+                    function + 1 + argument
+                },
+            };
+            let not_last = match term {
+                Term::Abstraction(_, _) => last + 2,
+                Term::Application(_, t2) => match **t2 {
+                    Term::Application(_, _) => last,
+                    _ => last - width(t2).0 + width(t2).1,
+                },
+                Term::Variable(_) => last,
+            };
+            widths.insert(term as *const Term, (last, not_last));
+        }
+        widths
+    }
+
+    /// Prints the term on a single line
+    fn flat(&self, out: &mut String, term: &Term, last: bool) {
+        let mut work = vec![Task::Layout(term, 0, last)];
+        while let Some(task) = work.pop() {
+            let (term, last) = match task {
+                Task::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                },
+                Task::Layout(term, _, last) => (term, last),
+                Task::Break(_) => unreachable!("flat terms have no line breaks"),
+            };
+
+            match term {
+                Term::Variable(name) => {
+                    out.push_str(name);
+                },
+                Term::Abstraction(_, _) if !last => {
+                    out.push('(');
+                    work.push(Task::Text(")"));
+                    work.push(Task::Layout(term, 0, true));
+                },
+                Term::Abstraction(_, _) => {
+                    let (names, body) = collapse(term);
+                    write!(out, "{}{}.", self.lambda, names.join(" ")).unwrap();
+                    work.push(Task::Layout(body, 0, true));
+                },
+                Term::Application(t1, t2) => {
+                    match **t2 {
+                        Term::Application(_, _) => {
+                            work.push(Task::Text(")"));
+                            work.push(Task::Layout(t2, 0, true));
+                            work.push(Task::Text(" ("));
+                        },
+                        _ => {
+                            work.push(Task::Layout(t2, 0, last));
+                            work.push(Task::Text(" "));
+                        },
+                    }
+                    match **t1 {
+                        Term::Abstraction(_, _) => {
+                            work.push(Task::Text(")"));
+                            work.push(Task::Layout(t1, 0, true));
+                            work.push(Task::Text("("));
+                        },
+                        _ => {
+                            work.push(Task::Layout(t1, 0, false));
+                        },
+                    }
+                },
+            }
+        }
+    }
+
+    fn flat_de_bruijn(&self, out: &mut String, term: &DeBruijn) {
+        let mut work = vec![Task::Layout(term, 0, true)];
+        while let Some(task) = work.pop() {
+            let (term, last) = match task {
+                Task::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                },
+                Task::Layout(term, _, last) => (term, last),
+                Task::Break(_) => unreachable!("flat terms have no line breaks"),
+            };
+
+            match term {
+                DeBruijn::Variable(index) => {
+                    out.push_str(&index.to_string());
+                },
+                DeBruijn::Abstraction(_) if !last => {
+                    out.push('(');
+                    work.push(Task::Text(")"));
+                    work.push(Task::Layout(term, 0, true));
+                },
+                DeBruijn::Abstraction(body) => {
+                    out.push_str(self.lambda);
+                    out.push('.');
+                    work.push(Task::Layout(body, 0, true));
+                },
+                DeBruijn::Application(t1, t2) => {
+                    match **t2 {
+                        DeBruijn::Application(_, _) => {
+                            work.push(Task::Text(")"));
+                            work.push(Task::Layout(t2, 0, true));
+                            work.push(Task::Text(" ("));
+                        },
+                        _ => {
+                            work.push(Task::Layout(t2, 0, last));
+                            work.push(Task::Text(" "));
+                        },
+                    }
+                    match **t1 {
+                        DeBruijn::Abstraction(_) => {
+                            work.push(Task::Text(")"));
+                            work.push(Task::Layout(t1, 0, true));
+                            work.push(Task::Text("("));
+                        },
+                        _ => {
+                            work.push(Task::Layout(t1, 0, false));
+                        },
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Printing left to do, kept on a stack in place of recursive calls
+enum Task<'t, T> {
+    Text(&'static str),
+    Break(usize),
+    /// Print the term starting at the given column, where `last` is true if nothing follows it
+    /// before the end of the enclosing parentheses
+    Layout(&'t T, usize, bool),
+}
+
+/// Splits nested abstractions into their bound variable names and innermost body
+fn collapse(term: &Term) -> (Vec<&str>, &Term) {
+    let mut names = Vec::new();
+    let mut body = term;
+    while let Term::Abstraction(name, inner) = body {
+        names.push(name.as_str());
+        body = inner;
+    }
+    (names, body)
+}
+
+/// Splits an application into the function at its head and its arguments, in order
+fn spine(term: &Term) -> (&Term, Vec<&Term>) {
+    let mut args = Vec::new();
+    let mut head = term;
+    while let Term::Application(t1, t2) = head {
+        args.push(&**t2);
+        head = t1;
+    }
+    args.reverse();
+    (head, args)
+}
+
+fn spaces(count: usize) -> String {
+    " ".repeat(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::testing::Random;
+    use crate::notation::conversion::remove_names;
+    use crate::parser::{parse_de_bruijn, parse_one};
+
+    fn assert_prints(expected: &str, expr: &str) {
+        assert_eq!(expected, Pretty::new().print(&parse_one(expr).unwrap()));
+    }
+
+    #[test]
+    fn test_does_not_parenthesize_abstraction_argument_at_end() {
+        assert_prints(r"(λx.x) λy.y", r"(\x.x) (\y.y)");
+    }
+
+    #[test]
+    fn test_parenthesizes_abstraction_followed_by_argument() {
+        assert_prints(r"f (λx.x) y", r"f (\x.x) y");
+    }
+
+    #[test]
+    fn test_parenthesizes_abstraction_at_end_of_function() {
+        assert_prints(r"f (λx.x) y", r"(f \x.x) y");
+    }
+
+    #[test]
+    fn test_does_not_parenthesize_left_nested_application() {
+        assert_prints(r"a b c", r"((a) b) c");
+    }
+
+    #[test]
+    fn test_parenthesizes_right_nested_application() {
+        assert_prints(r"a (b c)", r"a (b c)");
+    }
+
+    #[test]
+    fn test_collapses_nested_abstractions() {
+        assert_prints(r"λx y z.x z (y z)", r"\x.\y.\z.x z (y z)");
+    }
+
+    #[test]
+    fn test_breaks_long_application_between_arguments() {
+        assert_eq!(
+            "function\n  argument1\n  (λx.x)\n  argument2",
+            Pretty::with_width(20).print(&parse_one(r"function argument1 (\x.x) argument2").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_breaks_long_abstraction_after_dot() {
+        assert_eq!(
+            "λf x.\n  function argument",
+            Pretty::with_width(20).print(&parse_one(r"\f.\x.function argument").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_printed_terms_parse_to_same_term() {
+        let mut random = Random::new(0x9e77_1234_0000_0038);
+        for i in 0..1000 {
+            let term = random.term(6, &mut Vec::new());
+            let printed = Pretty::with_width(i % 40 + 1).print(&term);
+            assert_eq!(Some(term), parse_one(&printed).ok(), "printed as:\n{}", printed);
+        }
+    }
+
+    #[test]
+    fn test_prints_ascii_lambda() {
+        assert_eq!(r"\x y.x (\z.z) y", Pretty::new().ascii().print(&parse_one(r"\x.\y.x (\z.z) y").unwrap()));
+    }
+
+    #[test]
+    fn test_prints_de_bruijn_with_minimal_parentheses() {
+        assert_eq!(r"\.\.1 (\.0) (0 1)", Pretty::new().ascii().print_de_bruijn(&parse_de_bruijn(r"\.\.((1) (\.0)) (0 1)").unwrap()));
+    }
+
+    #[test]
+    fn test_printed_ascii_terms_parse_to_same_term() {
+        let mut random = Random::new(0x9e77_1234_0000_0039);
+        for i in 0..1000 {
+            let term = random.term(6, &mut Vec::new());
+            let printed = Pretty::with_width(i % 40 + 1).ascii().print(&term);
+            assert!(printed.is_ascii());
+            assert_eq!(Some(term), parse_one(&printed).ok(), "printed as:\n{}", printed);
+        }
+    }
+
+    #[test]
+    fn test_printed_de_bruijn_terms_parse_to_same_term() {
+        let mut random = Random::new(0x9e77_1234_0000_0039);
+        for _ in 0..1000 {
+            let term = remove_names(&random.term(6, &mut Vec::new()), &[]).unwrap();
+            for pretty in &[Pretty::new(), Pretty::new().ascii()] {
+                let printed = pretty.print_de_bruijn(&term);
+                assert_eq!(Some(term.clone()), parse_de_bruijn(&printed).ok(), "printed as: {}", printed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_flat_widths_match_printed_lengths() {
+        let mut random = Random::new(0x9e77_1234_0000_0047);
+        for _ in 0..500 {
+            let term = random.term(6, &mut Vec::new());
+            for pretty in &[Pretty::new(), Pretty::new().ascii()] {
+                let printed = pretty.print(&term);
+                assert_eq!(printed.chars().count(), pretty.flat_widths(&term)[&(&term as *const Term)].0);
+            }
+        }
+    }
+}
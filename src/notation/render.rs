@@ -0,0 +1,177 @@
+//! Rendering the abstract syntax tree of a term
+//!
+//! The `Display` impls print a term on a single line, which makes the structure of large terms
+//! hard to see. These renderers draw the tree itself, either as Graphviz DOT or as an ASCII tree
+//! for the terminal. With back edges enabled, each bound variable is also linked to the
+//! abstraction that binds it, which is the information a De Bruijn index encodes.
+use std::fmt::Write;
+
+use super::named::Term;
+
+pub struct Renderer {
+    back_edges: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer { back_edges: false }
+    }
+
+    /// Links bound variables to their binders
+    pub fn with_back_edges() -> Renderer {
+        Renderer { back_edges: true }
+    }
+
+    /// Renders the tree in Graphviz DOT format. Nodes are numbered in the order they're written,
+    /// and each node's edges are written after its subtree.
+    pub fn to_dot(&self, term: &Term) -> String {
+        enum Work<'t> {
+            Enter(&'t Term),
+            ExitAbstraction(usize),
+            ExitApplication(usize),
+        }
+
+        let mut dot = String::from("digraph term {\n");
+        let mut binders: Vec<(&str, usize)> = Vec::new();
+        let mut next_id = 0;
+        // The ids of the subtrees written so far whose edges from their parent aren't yet
+        let mut children = Vec::new();
+        let mut work = vec![Work::Enter(term)];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(term) => {
+                    let id = next_id;
+                    next_id += 1;
+                    match term {
+                        Term::Variable(name) => {
+                            writeln!(dot, "  n{} [label=\"{}\", shape=plaintext];", id, name).unwrap();
+                            if self.back_edges {
+                                if let Some((_, binder)) = binders.iter().rev().find(|(n, _)| n == name) {
+                                    writeln!(dot, "  n{} -> n{} [style=dashed, constraint=false];", id, binder).unwrap();
+                                }
+                            }
+                            children.push(id);
+                        },
+                        Term::Abstraction(name, body) => {
+                            writeln!(dot, "  n{} [label=\"λ{}\"];", id, name).unwrap();
+                            binders.push((name, id));
+                            work.push(Work::ExitAbstraction(id));
+                            work.push(Work::Enter(body));
+                        },
+                        Term::Application(t1, t2) => {
+                            writeln!(dot, "  n{} [label=\"@\"];", id).unwrap();
+                            work.push(Work::ExitApplication(id));
+                            work.push(Work::Enter(t2));
+                            work.push(Work::Enter(t1));
+                        },
+                    }
+                },
+                Work::ExitAbstraction(id) => {
+                    binders.pop();
+                    let child = children.pop().expect("abstraction has a body");
+                    writeln!(dot, "  n{} -> n{};", id, child).unwrap();
+                    children.push(id);
+                },
+                Work::ExitApplication(id) => {
+                    let child2 = children.pop().expect("application has an argument");
+                    let child1 = children.pop().expect("application has a function");
+                    writeln!(dot, "  n{} -> n{};", id, child1).unwrap();
+                    writeln!(dot, "  n{} -> n{};", id, child2).unwrap();
+                    children.push(id);
+                },
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the tree as indented ASCII art, one node per line
+    pub fn to_tree(&self, term: &Term) -> String {
+        /// A subtree still to write, with the length of the prefix its parent's subtree has, the
+        /// connector for its own line and what its subtree's lines add to the prefix
+        enum Work<'t> {
+            Enter(&'t Term, usize, &'static str, &'static str),
+            ExitAbstraction,
+        }
+
+        let mut tree = String::new();
+        let mut binders: Vec<&str> = Vec::new();
+        let mut prefix = String::new();
+        let mut work = vec![Work::Enter(term, 0, "", "")];
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Enter(term, parent_prefix, connector, indent) => {
+                    prefix.truncate(parent_prefix);
+                    write!(tree, "{}{}", prefix, connector).unwrap();
+                    prefix.push_str(indent);
+                    match term {
+                        Term::Variable(name) => {
+                            match binders.iter().rev().position(|n| n == name) {
+                                Some(index) if self.back_edges => writeln!(tree, "{} -> {}", name, index).unwrap(),
+                                _ => writeln!(tree, "{}", name).unwrap(),
+                            }
+                        },
+                        Term::Abstraction(name, body) => {
+                            writeln!(tree, "λ{}", name).unwrap();
+                            binders.push(name);
+                            work.push(Work::ExitAbstraction);
+                            work.push(Work::Enter(body, prefix.len(), "`-- ", "    "));
+                        },
+                        Term::Application(t1, t2) => {
+                            writeln!(tree, "@").unwrap();
+                            work.push(Work::Enter(t2, prefix.len(), "`-- ", "    "));
+                            work.push(Work::Enter(t1, prefix.len(), "|-- ", "|   "));
+                        },
+                    }
+                },
+                Work::ExitAbstraction => {
+                    binders.pop();
+                },
+            }
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_one;
+
+    #[test]
+    fn test_renders_tree() {
+        assert_eq!(
+            "@\n|-- λx\n|   `-- @\n|       |-- x\n|       `-- y\n`-- z\n",
+            Renderer::new().to_tree(&parse_one(r"(\x.x y) z").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_renders_tree_with_back_edges() {
+        assert_eq!(
+            "λx\n`-- λy\n    `-- @\n        |-- x -> 1\n        `-- z\n",
+            Renderer::with_back_edges().to_tree(&parse_one(r"\x.\y.x z").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_renders_dot() {
+        assert_eq!(
+            "digraph term {\n  n0 [label=\"λx\"];\n  n1 [label=\"x\", shape=plaintext];\n  n0 -> n1;\n}\n",
+            Renderer::new().to_dot(&parse_one(r"\x.x").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_renders_dot_back_edge_to_nearest_binder() {
+        let dot = Renderer::with_back_edges().to_dot(&parse_one(r"\x.\x.x").unwrap());
+        assert!(dot.contains("n2 -> n1 [style=dashed, constraint=false];"));
+        assert!(!dot.contains("n2 -> n0"));
+    }
+}
@@ -0,0 +1,454 @@
+//! Hash-consed De Bruijn terms
+//!
+//! Terms are built through a `Store`, which interns every node: building a node equal to one that
+//! is still in use returns the existing one. Equal terms are therefore the same allocation, so
+//! cloning a term and comparing two terms are O(1), and a term that mentions the same subterm many
+//! times, like a Church numeral applied to itself, only stores it once.
+//!
+//! Each node caches its size, its free variables and whether it is in normal form. Substitution
+//! uses these to return untouched subterms as they are instead of copying them, and remembers the
+//! result for every node it visits so a shared subterm is only substituted into once.
+//!
+//! Only the `SharedNormal` strategy works on these terms. The other strategies still step named
+//! terms, cloning the subtrees they copy.
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::{Rc, Weak},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::DeBruijn;
+
+/// A term interned in a `Store`. Comparing terms from different stores panics in debug builds.
+#[derive(Clone)]
+pub struct Term(Rc<Data>);
+
+struct Data {
+    /// Identifies the store the node was interned in
+    store: usize,
+    node: Node,
+    size: usize,
+    free: Rc<[u32]>,
+    normal: bool,
+}
+
+pub enum Node {
+    Variable(u32),
+    Abstraction(Term),
+    Application(Term, Term),
+}
+
+impl Term {
+    pub fn node(&self) -> &Node {
+        &self.0.node
+    }
+
+    /// Returns the number of nodes in this term, counting shared subterms once per occurrence
+    pub fn size(&self) -> usize {
+        self.0.size
+    }
+
+    /// Returns the indices of the free variables of this term, in increasing order
+    pub fn free_variables(&self) -> &[u32] {
+        &self.0.free
+    }
+
+    /// Returns true if every free variable of this term has an index below `depth`, so that
+    /// substituting or shifting at or above `depth` leaves it unchanged
+    pub fn is_closed_under(&self, depth: u32) -> bool {
+        self.0.free.last().is_none_or(|index| *index < depth)
+    }
+
+    /// Returns true if this term contains no redexes, including inside abstractions
+    pub fn is_normal_form(&self) -> bool {
+        self.0.normal
+    }
+
+    /// Returns the address of the node, which identifies it within its store
+    fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+/// Terms are only equal when they're the same node, which is only true of equal terms when they
+/// come from the same store
+impl PartialEq for Term {
+    fn eq(&self, other: &Term) -> bool {
+        debug_assert_eq!(self.0.store, other.0.store, "compared terms from different stores");
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Term {}
+
+impl Hash for Term {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+impl From<&Term> for DeBruijn {
+    fn from(term: &Term) -> DeBruijn {
+        match term.node() {
+            Node::Variable(index) => DeBruijn::Variable(*index),
+            Node::Abstraction(body) => DeBruijn::Abstraction(box DeBruijn::from(body)),
+            Node::Application(t1, t2) => DeBruijn::Application(box DeBruijn::from(t1), box DeBruijn::from(t2)),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", DeBruijn::from(self))
+    }
+}
+
+impl fmt::Debug for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Shared({})", DeBruijn::from(self))
+    }
+}
+
+/// Identifies a node by its variable index or the nodes it's made of
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    Variable(u32),
+    Abstraction(usize),
+    Application(usize, usize),
+}
+
+/// The nodes of every term built so far.
+///
+/// The store only holds weak references, so a node is freed once no term uses it. A key made of
+/// child addresses stays valid while its node is alive, since the node keeps its children alive.
+pub struct Store {
+    id: usize,
+    nodes: HashMap<Key, Weak<Data>>,
+    /// Number of entries after which dead ones are removed
+    prune_at: usize,
+}
+
+/// Smallest number of entries at which the store removes dead ones
+const MIN_PRUNE: usize = 1024;
+
+/// The id of the next store created
+static NEXT_STORE: AtomicUsize = AtomicUsize::new(0);
+
+impl Default for Store {
+    fn default() -> Store {
+        Store::new()
+    }
+}
+
+impl Store {
+    pub fn new() -> Store {
+        Store {
+            id: NEXT_STORE.fetch_add(1, Ordering::Relaxed),
+            nodes: HashMap::new(),
+            prune_at: MIN_PRUNE,
+        }
+    }
+
+    /// Returns the number of distinct nodes in use
+    pub fn len(&self) -> usize {
+        self.nodes.values().filter(|node| node.strong_count() > 0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn variable(&mut self, index: u32) -> Term {
+        let store = self.id;
+        self.intern(Key::Variable(index), || {
+            Data {
+                store,
+                node: Node::Variable(index),
+                size: 1,
+                free: Rc::new([index]),
+                normal: true,
+            }
+        })
+    }
+
+    pub fn abstraction(&mut self, body: Term) -> Term {
+        let store = self.id;
+        self.intern(Key::Abstraction(body.id()), || {
+            let free: Vec<u32> = body.free_variables().iter().filter(|index| **index > 0).map(|index| index - 1).collect();
+            Data {
+                store,
+                size: body.size() + 1,
+                free: free.into(),
+                normal: body.is_normal_form(),
+                node: Node::Abstraction(body),
+            }
+        })
+    }
+
+    pub fn application(&mut self, func: Term, arg: Term) -> Term {
+        let store = self.id;
+        self.intern(Key::Application(func.id(), arg.id()), || {
+            Data {
+                store,
+                size: func.size() + arg.size() + 1,
+                free: union(&func.0.free, &arg.0.free),
+                normal: func.is_normal_form() && arg.is_normal_form() && !matches!(func.node(), Node::Abstraction(_)),
+                node: Node::Application(func, arg),
+            }
+        })
+    }
+
+    fn intern(&mut self, key: Key, data: impl FnOnce() -> Data) -> Term {
+        if let Some(node) = self.nodes.get(&key).and_then(Weak::upgrade) {
+            return Term(node);
+        }
+        let node = Rc::new(data());
+        self.nodes.insert(key, Rc::downgrade(&node));
+        if self.nodes.len() >= self.prune_at {
+            self.nodes.retain(|_, node| node.strong_count() > 0);
+            self.prune_at = MIN_PRUNE.max(2 * self.nodes.len());
+        }
+        Term(node)
+    }
+
+    /// Interns a De Bruijn term
+    pub fn import(&mut self, term: &DeBruijn) -> Term {
+        match term {
+            DeBruijn::Variable(index) => {
+                self.variable(*index)
+            },
+            DeBruijn::Abstraction(body) => {
+                let body = self.import(body);
+                self.abstraction(body)
+            },
+            DeBruijn::Application(t1, t2) => {
+                let t1 = self.import(t1);
+                let t2 = self.import(t2);
+                self.application(t1, t2)
+            },
+        }
+    }
+
+    /// Returns the result of contracting the redex `(λ.body) arg`
+    pub fn instantiate(&mut self, body: &Term, arg: &Term) -> Term {
+        Instantiate {
+            store: self,
+            arg: arg.clone(),
+            shifted_args: HashMap::new(),
+            done: HashMap::new(),
+        }.call(body, 0)
+    }
+
+    /// Returns `term` with all free variable indices at or above `cutoff` increased by `amount`
+    pub fn shift(&mut self, term: &Term, amount: u32, cutoff: u32) -> Term {
+        Shift {
+            store: self,
+            amount,
+            done: HashMap::new(),
+        }.call(term, cutoff)
+    }
+}
+
+/// Merges two sorted lists of indices, reusing one of them if it already contains the other
+fn union(left: &Rc<[u32]>, right: &Rc<[u32]>) -> Rc<[u32]> {
+    if right.iter().all(|index| left.binary_search(index).is_ok()) {
+        return left.clone();
+    }
+    if left.iter().all(|index| right.binary_search(index).is_ok()) {
+        return right.clone();
+    }
+    let mut merged: Vec<u32> = left.iter().chain(right.iter()).cloned().collect();
+    merged.sort();
+    merged.dedup();
+    merged.into()
+}
+
+/// Replaces index 0 of an abstraction body with an argument, memoized per node and depth
+struct Instantiate<'s> {
+    store: &'s mut Store,
+    arg: Term,
+    shifted_args: HashMap<u32, Term>,
+    done: HashMap<(Term, u32), Term>,
+}
+
+impl Instantiate<'_> {
+    fn call(&mut self, term: &Term, depth: u32) -> Term {
+        if term.is_closed_under(depth) {
+            return term.clone();
+        }
+        let key = (term.clone(), depth);
+        if let Some(result) = self.done.get(&key) {
+            return result.clone();
+        }
+
+        let result = match term.node() {
+            Node::Variable(index) if *index == depth => {
+                self.shifted_arg(depth)
+            },
+            Node::Variable(index) => {
+                // Indices below `depth` are bound inside the body, so this one is free
+                self.store.variable(index - 1)
+            },
+            Node::Abstraction(body) => {
+                let body = self.call(body, depth + 1);
+                self.store.abstraction(body)
+            },
+            Node::Application(t1, t2) => {
+                let t1 = self.call(t1, depth);
+                let t2 = self.call(t2, depth);
+                self.store.application(t1, t2)
+            },
+        };
+        self.done.insert(key, result.clone());
+        result
+    }
+
+    /// Returns the argument as it should appear under `depth` binders of the body
+    fn shifted_arg(&mut self, depth: u32) -> Term {
+        if let Some(arg) = self.shifted_args.get(&depth) {
+            return arg.clone();
+        }
+        let arg = self.store.shift(&self.arg, depth, 0);
+        self.shifted_args.insert(depth, arg.clone());
+        arg
+    }
+}
+
+/// Increases free variable indices by a fixed amount, memoized per node and cutoff
+struct Shift<'s> {
+    store: &'s mut Store,
+    amount: u32,
+    done: HashMap<(Term, u32), Term>,
+}
+
+impl Shift<'_> {
+    fn call(&mut self, term: &Term, cutoff: u32) -> Term {
+        if self.amount == 0 || term.is_closed_under(cutoff) {
+            return term.clone();
+        }
+        let key = (term.clone(), cutoff);
+        if let Some(result) = self.done.get(&key) {
+            return result.clone();
+        }
+
+        let result = match term.node() {
+            Node::Variable(index) => {
+                self.store.variable(index + self.amount)
+            },
+            Node::Abstraction(body) => {
+                let body = self.call(body, cutoff + 1);
+                self.store.abstraction(body)
+            },
+            Node::Application(t1, t2) => {
+                let t1 = self.call(t1, cutoff);
+                let t2 = self.call(t2, cutoff);
+                self.store.application(t1, t2)
+            },
+        };
+        self.done.insert(key, result.clone());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notation::Notation;
+    use crate::notation::de_bruijn::{a, l};
+    use crate::evaluation::testing::Random;
+    use crate::parser::parse_de_bruijn;
+    use super::*;
+
+    #[test]
+    pub fn test_equal_terms_share_a_node() {
+        let mut store = Store::new();
+        let t1 = store.import(&parse_de_bruijn(r"\.0 (\.0)").unwrap());
+        let t2 = store.import(&parse_de_bruijn(r"\.0 (\.0)").unwrap());
+        assert_eq!(t1, t2);
+        assert!(Rc::ptr_eq(&t1.0, &t2.0));
+        // `\.0 (\.0)` has the nodes `0`, `\.0`, `0 (\.0)` and `\.0 (\.0)`
+        assert_eq!(4, store.len());
+    }
+
+    #[test]
+    pub fn test_different_terms_are_not_equal() {
+        let mut store = Store::new();
+        let t1 = store.import(&parse_de_bruijn(r"\.0 1").unwrap());
+        let t2 = store.import(&parse_de_bruijn(r"\.1 0").unwrap());
+        assert_ne!(t1, t2);
+    }
+
+    #[test]
+    #[should_panic(expected = "compared terms from different stores")]
+    #[cfg(debug_assertions)]
+    pub fn test_comparing_terms_from_different_stores_panics() {
+        let t1 = Store::new().variable(0);
+        let t2 = Store::new().variable(0);
+        let _ = t1 == t2;
+    }
+
+    #[test]
+    pub fn test_caches_free_variables_and_size() {
+        let mut store = Store::new();
+        let term = store.import(&parse_de_bruijn(r"\.0 3 (\.2 0)").unwrap());
+        assert_eq!(&[0, 2], term.free_variables());
+        assert_eq!(9, term.size());
+        assert!(term.is_closed_under(3));
+        assert!(!term.is_closed_under(2));
+    }
+
+    #[test]
+    pub fn test_caches_normal_form() {
+        let mut store = Store::new();
+        assert!(store.import(&parse_de_bruijn(r"\.0 (\.0)").unwrap()).is_normal_form());
+        assert!(!store.import(&parse_de_bruijn(r"\.0 ((\.0) 0)").unwrap()).is_normal_form());
+    }
+
+    #[test]
+    pub fn test_instantiate_shifts_argument_under_binders() {
+        let mut store = Store::new();
+        let body = store.import(&parse_de_bruijn(r"\.1 0 2").unwrap());
+        let arg = store.import(&parse_de_bruijn(r"0").unwrap());
+        assert_eq!(
+            parse_de_bruijn(r"\.1 0 1").unwrap(),
+            DeBruijn::from(&store.instantiate(&body, &arg))
+        );
+    }
+
+    #[test]
+    pub fn test_instantiate_keeps_closed_subterms() {
+        let mut store = Store::new();
+        let closed = store.import(&parse_de_bruijn(r"\.\.1 0").unwrap());
+        let variable = store.variable(0);
+        let body = store.application(closed.clone(), variable);
+        let arg = store.import(&parse_de_bruijn(r"\.0").unwrap());
+        match store.instantiate(&body, &arg).node() {
+            Node::Application(t1, _) => assert!(Rc::ptr_eq(&closed.0, &t1.0)),
+            _ => panic!("expected an application"),
+        }
+    }
+
+    #[test]
+    pub fn test_agrees_with_de_bruijn_instantiate() {
+        let mut random = Random::new(0x54a4_ed00_0000_0046);
+        let mut store = Store::new();
+        for _ in 0..500 {
+            let body = DeBruijn::from(&random.term_over(5, &mut vec!["a", "b", "x"], &["a", "b", "x", "y"]));
+            let arg = DeBruijn::from(&random.term_over(4, &mut vec!["a", "b"], &["a", "b", "y"]));
+            let shared_body = store.import(&body);
+            let shared_arg = store.import(&arg);
+            assert_eq!(
+                DeBruijn::instantiate(&(), &body, &arg),
+                DeBruijn::from(&store.instantiate(&shared_body, &shared_arg))
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_import_round_trips() {
+        let mut store = Store::new();
+        let term = l(a(a(1u32, l(0u32)), a(l(0u32), 0u32)));
+        assert_eq!(term, DeBruijn::from(&store.import(&term)));
+    }
+}
@@ -4,6 +4,7 @@ use std::{
 
 use pest::{
     Parser as PestParser,
+    Position,
     iterators::Pair
 };
 
@@ -11,6 +12,7 @@ use failure::{
     Fail,
 };
 
+use crate::notation::{DeBruijn, Notation};
 use crate::notation::named::Term;
 
 #[derive(pest_derive::Parser)]
@@ -23,7 +25,8 @@ pub struct Parser<'p> {
 }
 
 #[derive(Debug, Fail)]
-enum ParseError {
+#[non_exhaustive]
+pub enum ParseError {
     #[fail(display = "empty input")]
     EmptyInput,
 
@@ -31,10 +34,27 @@ enum ParseError {
     NoTerms,
 
     #[fail(display = "failed to parse: {}", _0)]
-    PestError(pest::error::Error<Rule>),
+    Syntax(String),
 
+    /// The grammar produced a pair the parser didn't expect
+    #[doc(hidden)]
     #[fail(display = "parse error for pair: {}", _0)]
     Unknown(&'static str),
+
+    #[fail(display = "index out of range: {}", _0)]
+    IndexOutOfRange(String),
+
+    #[fail(display = "unmatched closing parenthesis at line {}, column {}", _0, _1)]
+    UnmatchedParenthesis(usize, usize),
+
+    #[fail(display = "unclosed parenthesis at line {}, column {}", _0, _1)]
+    UnclosedParenthesis(usize, usize),
+
+    #[fail(display = "empty parentheses at line {}, column {}", _0, _1)]
+    EmptyParentheses(usize, usize),
+
+    #[fail(display = "abstraction without a body at line {}, column {}", _0, _1)]
+    MissingBody(usize, usize),
 }
 
 pub fn parse(text: &str) -> Result<Vec<Term>, failure::Error> {
@@ -47,6 +67,140 @@ pub fn parse_one(text: &str) -> Result<Term, failure::Error> {
     parser.parse(text).and_then(|mut iter| iter.next().ok_or(ParseError::NoTerms.into()))
 }
 
+/// Parses a single term written in De Bruijn notation, such as `\. \. 1 0`
+pub fn parse_de_bruijn(text: &str) -> Result<DeBruijn, failure::Error> {
+    let pair = LambdaCalculusParser::parse(Rule::de_bruijn_main, text)
+        .map_err(|e| ParseError::Syntax(e.to_string()))?
+        .next()
+        .and_then(|main| main.into_inner().next())
+        .ok_or(ParseError::EmptyInput)?;
+
+    Ok(process_de_bruijn_term(pair)?)
+}
+
+fn process_de_bruijn_term(pair: Pair<'_, Rule>) -> Result<DeBruijn, ParseError> {
+    let mut builder = Builder::new();
+    for token in pair.into_inner() {
+        let position = token.as_span().start_pos();
+        match token.as_rule() {
+            Rule::index => {
+                let index = token.as_str()
+                    .parse()
+                    .map_err(|_| ParseError::IndexOutOfRange(token.as_str().to_string()))?;
+                builder.term(DeBruijn::Variable(index));
+            },
+            Rule::de_bruijn_binder => builder.binders(vec![()], position),
+            Rule::open => builder.open(position),
+            Rule::close => builder.close(position)?,
+            rule => unreachable!("{:?}", rule),
+        }
+    }
+    builder.finish()
+}
+
+/// Puts a term together from its tokens, keeping the abstractions and parentheses that are still
+/// open on a stack rather than recursing into them
+struct Builder<'i, T: Notation> {
+    /// The innermost open abstraction or parentheses is last. The first frame is the whole term.
+    frames: Vec<Frame<'i, T>>,
+}
+
+struct Frame<'i, T: Notation> {
+    /// The binders of the abstraction this frame is the body of, or none for parentheses
+    binders: Vec<T::Binder>,
+    parenthesized: bool,
+    /// The application of the subterms read so far
+    term: Option<T>,
+    /// Where the frame starts, for error messages, or `None` for the whole term
+    position: Option<Position<'i>>,
+}
+
+impl<'i, T: Notation> Frame<'i, T> {
+    fn new(binders: Vec<T::Binder>, parenthesized: bool, position: Option<Position<'i>>) -> Frame<'i, T> {
+        Frame { binders, parenthesized, term: None, position }
+    }
+
+    fn finish(self) -> Result<T, ParseError> {
+        let body = match self.term {
+            Some(term) => term,
+            None => {
+                let (line, column) = line_col(self.position);
+                return Err(if self.parenthesized {
+                    ParseError::EmptyParentheses(line, column)
+                } else {
+                    ParseError::MissingBody(line, column)
+                });
+            },
+        };
+        Ok(self.binders.into_iter().rev().fold(body, |body, binder| T::abstraction(binder, body)))
+    }
+}
+
+/// Returns the line and column of a position. This scans the input up to the position, so is
+/// only done when reporting an error.
+fn line_col(position: Option<Position<'_>>) -> (usize, usize) {
+    position.map_or((1, 1), |position| position.line_col())
+}
+
+impl<'i, T: Notation> Builder<'i, T> {
+    fn new() -> Builder<'i, T> {
+        Builder { frames: vec![Frame::new(Vec::new(), false, None)] }
+    }
+
+    /// Adds a subterm, applying the term read so far to it
+    fn term(&mut self, term: T) {
+        let frame = self.frames.last_mut().expect("the whole term's frame is never closed");
+        frame.term = Some(match frame.term.take() {
+            Some(function) => T::application(function, term),
+            None => term,
+        });
+    }
+
+    fn binders(&mut self, binders: Vec<T::Binder>, position: Position<'i>) {
+        self.frames.push(Frame::new(binders, false, Some(position)));
+    }
+
+    fn open(&mut self, position: Position<'i>) {
+        self.frames.push(Frame::new(Vec::new(), true, Some(position)));
+    }
+
+    /// Closes the innermost parentheses, along with the abstractions inside them
+    fn close(&mut self, position: Position<'i>) -> Result<(), ParseError> {
+        loop {
+            if self.frames.len() == 1 {
+                let (line, column) = position.line_col();
+                return Err(ParseError::UnmatchedParenthesis(line, column));
+            }
+            let frame = self.frames.pop().expect("there is a frame to close");
+            let parenthesized = frame.parenthesized;
+            let term = frame.finish()?;
+            self.term(term);
+            if parenthesized {
+                return Ok(());
+            }
+        }
+    }
+
+    fn finish(mut self) -> Result<T, ParseError> {
+        while self.frames.len() > 1 {
+            let frame = self.frames.pop().expect("there is a frame to close");
+            if frame.parenthesized {
+                let (line, column) = line_col(frame.position);
+                return Err(ParseError::UnclosedParenthesis(line, column));
+            }
+            let term = frame.finish()?;
+            self.term(term);
+        }
+        self.frames.pop().expect("the whole term has a frame").finish()
+    }
+}
+
+impl <'p> Default for Parser<'p> {
+    fn default() -> Parser<'p> {
+        Parser::new()
+    }
+}
+
 impl <'p> Parser<'p> {
     pub fn new() -> Parser<'p> {
         Parser {
@@ -57,14 +211,19 @@ impl <'p> Parser<'p> {
 
     pub fn parse(&mut self, text: &'p str) -> Result<impl Iterator<Item=Term> + '_, failure::Error> {
         let pair = LambdaCalculusParser::parse(Rule::main, &text)
-            .map_err(|e| ParseError::PestError(e))
+            .map_err(|e| ParseError::Syntax(e.to_string()))
             .map(|mut pairs| pairs.next())?
             .ok_or(ParseError::EmptyInput)?;
 
-        self.process_pair(pair);
+        self.process_pair(pair)?;
         Ok(self.terms.drain(0..))
     }
 
+    /// Returns the macros defined so far, by name
+    pub fn macros(&self) -> &HashMap<&'p str, Term> {
+        &self.macros
+    }
+
     fn process_pair(&mut self, pair: Pair<'p, Rule>) -> Result<(), ParseError> {
         match pair.as_rule() {
             Rule::main  => {
@@ -86,43 +245,28 @@ impl <'p> Parser<'p> {
     }
 
     fn process_term(&mut self, pair: Pair<'p, Rule>) -> Result<Term, ParseError> {
-        match pair.as_rule() {
-            Rule::term | Rule::simple_term => {
-                self.process_term(pair.into_inner().next().ok_or(ParseError::Unknown("term"))?)
-            },
-            Rule::variable => {
-                let name = pair.as_str();
-                Ok(
-                    self.macros.get(name)
-                               .map(|t| t.clone())
-                               .unwrap_or_else(|| Term::Variable(name.to_string()))
-                )
-            },
-            Rule::abstraction => {
-                let mut pairs = pair.into_inner();
-                Ok(Term::Abstraction(
-                    pairs.next().map(|p| p.as_str().to_string()).ok_or(ParseError::Unknown("abstraction[0]"))?,
-                    Box::new(
-                        pairs.next()
-                             .ok_or(ParseError::Unknown("abstraction[1]"))
-                             .map(|t| self.process_term(t))??
-                    )
-                ))
-            },
-            Rule::application => {
-                let mut pairs = pair.into_inner();
-                let t1 =
-                        pairs.next()
-                             .ok_or(ParseError::Unknown("application[0]"))
-                             .map(|t| self.process_term(t))??;
-
-                pairs.try_fold(t1, |app, t| {
-                    let term = self.process_term(t)?;
-                    Ok(Term::Application(box app, box term))
-                })
-            },
-            rule => unreachable!("{:?}", rule),
+        let mut builder = Builder::new();
+        for token in pair.into_inner() {
+            let position = token.as_span().start_pos();
+            match token.as_rule() {
+                Rule::variable => {
+                    let name = token.as_str();
+                    builder.term(
+                        self.macros.get(name)
+                                   .map(|t| t.clone())
+                                   .unwrap_or_else(|| Term::Variable(name.to_string()))
+                    );
+                },
+                Rule::binder => {
+                    let names = token.into_inner().map(|name| name.as_str().to_string()).collect();
+                    builder.binders(names, position);
+                },
+                Rule::open => builder.open(position),
+                Rule::close => builder.close(position)?,
+                rule => unreachable!("{:?}", rule),
+            }
         }
+        builder.finish()
     }
 
     fn read_macro(&mut self, pair: Pair<'p, Rule>) -> Result<(), ParseError> {
@@ -240,6 +384,88 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_parses_abstraction_with_lambda_character() {
+        assert_eq(
+            r"λx.x",
+            l("x", "x")
+        );
+    }
+
+    #[test]
+    pub fn test_parses_abstraction_with_several_variables() {
+        assert_eq(
+            r"\x y z. x z",
+            l("x", l("y", l("z", a("x", "z"))))
+        );
+    }
+
+    #[test]
+    pub fn test_parses_indented_line_as_continuation() {
+        assert_eq(
+            "\\x.\n  x\n    y",
+            l("x", a("x", "y"))
+        );
+    }
+
+    #[test]
+    pub fn test_parses_de_bruijn_term() {
+        use crate::notation::de_bruijn::{l, a};
+        assert_eq!(
+            l(l(a(a(1u32, 0u32), l(0u32)))),
+            parse_de_bruijn(r"\. \. 1 0 (λ.0)").unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_does_not_parse_named_variable_as_de_bruijn_term() {
+        assert!(parse_de_bruijn(r"\. x").is_err());
+    }
+
+    fn parse_error(code: &str) -> ParseError {
+        parse_one(code).expect_err("expected a parse error").downcast().unwrap()
+    }
+
+    #[test]
+    pub fn test_abstraction_extends_to_closing_parenthesis() {
+        assert_eq(
+            r"f (\x.\y.x y) z",
+            a(a("f", l("x", l("y", a("x", "y")))), "z")
+        );
+    }
+
+    #[test]
+    pub fn test_reports_unmatched_closing_parenthesis() {
+        match parse_error(r"(x y) z)") {
+            ParseError::UnmatchedParenthesis(1, 8) => {},
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    pub fn test_reports_unclosed_parenthesis() {
+        match parse_error(r"x (\y.(y") {
+            ParseError::UnclosedParenthesis(1, 7) => {},
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    pub fn test_reports_abstraction_without_body() {
+        match parse_error(r"f (\x.)") {
+            ParseError::MissingBody(1, 4) => {},
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    pub fn test_reports_empty_parentheses() {
+        match parse_error(r"f ()") {
+            ParseError::EmptyParentheses(1, 3) => {},
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
     #[test]
     pub fn test_parses_and_substitutes_macro() {
         assert_eq(
@@ -268,4 +494,67 @@ mod tests {
             a(a(a(l("x", "x"), "a"), "b"), "c").is_redex()
         );
     }
+
+    #[test]
+    pub fn test_is_alpha_equivalent_true_for_renamed_bound_variables() {
+        assert!(
+            l("x", l("y", a("x", "z"))).is_alpha_equivalent(&l("y", l("x", a("y", "z"))))
+        );
+    }
+
+    #[test]
+    pub fn test_is_alpha_equivalent_false_for_different_free_variables() {
+        assert!(
+            !l("x", a("x", "y")).is_alpha_equivalent(&l("x", a("x", "z")))
+        );
+    }
+
+    #[test]
+    pub fn test_is_alpha_equivalent_false_for_captured_variable() {
+        assert!(
+            !l("x", l("y", "x")).is_alpha_equivalent(&l("x", l("x", "x")))
+        );
+    }
+
+    #[test]
+    pub fn test_is_normal_form_false_for_redex_under_abstraction() {
+        assert!(
+            !l("x", a("x", a(l("y", "y"), "z"))).is_normal_form()
+        );
+    }
+
+    #[test]
+    pub fn test_is_normal_form_true_for_variable_applied_to_abstraction() {
+        assert!(
+            a("x", l("y", a("y", "y"))).is_normal_form()
+        );
+    }
+
+    #[test]
+    pub fn test_is_head_normal_form_true_with_redex_in_argument() {
+        assert!(
+            l("x", a("x", a(l("y", "y"), "z"))).is_head_normal_form()
+        );
+    }
+
+    #[test]
+    pub fn test_is_head_normal_form_false_for_redex_at_head_under_abstraction() {
+        assert!(
+            !l("x", a(a(l("y", "y"), "x"), "z")).is_head_normal_form()
+        );
+    }
+
+    #[test]
+    pub fn test_is_whnf_true_for_abstraction_with_redex_at_head() {
+        assert!(
+            l("x", a(l("y", "y"), "x")).is_whnf()
+        );
+    }
+
+    #[test]
+    pub fn test_is_whnf_false_for_redex() {
+        assert!(
+            !a(a(l("y", "y"), "x"), "z").is_whnf()
+        );
+    }
 }
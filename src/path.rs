@@ -0,0 +1,343 @@
+//! Addressing subterms by their path from the root of a term
+//!
+//! A path is the sequence of steps taken from the root to reach a subterm: into the function or
+//! argument of an application, or into the body of an abstraction. Paths let us say exactly which
+//! redex a strategy contracts, where `is_redex` can only say whether there is one.
+use crate::notation::named::{Parts, Term};
+use crate::substitution::Substitutable;
+
+/// A single step from a term to one of its immediate subterms
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dir {
+    /// The function of an application
+    Fun,
+
+    /// The argument of an application
+    Arg,
+
+    /// The body of an abstraction
+    Body,
+}
+
+pub type Path = Vec<Dir>;
+
+/// Returns the paths to every redex in the term, in leftmost outermost order
+pub fn redex_paths(term: &Term) -> Vec<Path> {
+    let mut result = Vec::new();
+    visit_redexes(term, |path| {
+        result.push(path.to_vec());
+        false
+    });
+    result
+}
+
+/// Returns the path to the leftmost outermost redex in the term, the one normal order contracts
+pub fn leftmost_outermost_redex(term: &Term) -> Option<Path> {
+    let mut result = None;
+    visit_redexes(term, |path| {
+        result = Some(path.to_vec());
+        true
+    });
+    result
+}
+
+/// Returns the path to the leftmost outermost redex in a term just produced by contracting its
+/// leftmost outermost redex, at the path `contracted`, or `None` if it has no redexes.
+///
+/// Nothing before the contracted redex in leftmost outermost order was a redex or contained one,
+/// and contracting it can only turn its parent into a redex, so the search resumes from where the
+/// contracted redex was instead of starting again from the root.
+pub fn next_leftmost_outermost_redex(term: &Term, contracted: &[Dir]) -> Option<Path> {
+    let mut ancestors = Vec::with_capacity(contracted.len());
+    let mut focus = term;
+    for dir in contracted {
+        ancestors.push(focus);
+        focus = subterm_at(focus, &[*dir])?;
+    }
+
+    if let (Some(Dir::Fun), Some(Term::Application(box Term::Abstraction(_, _), _))) = (contracted.last(), ancestors.last()) {
+        return Some(contracted[..contracted.len() - 1].to_vec());
+    }
+
+    // The arguments of the applications we went into the function of are still to be searched,
+    // after the contracted subterm itself
+    let mut work = Vec::new();
+    for (depth, (ancestor, dir)) in ancestors.into_iter().zip(contracted).enumerate() {
+        if let (Term::Application(_, t2), Dir::Fun) = (ancestor, dir) {
+            work.push((&**t2, depth, Some(Dir::Arg)));
+        }
+    }
+    work.push((focus, contracted.len(), None));
+
+    let mut result = None;
+    visit_redexes_from(work, contracted.to_vec(), |path| {
+        result = Some(path.to_vec());
+        true
+    });
+    result
+}
+
+/// Calls `found` with the path to each redex in leftmost outermost order, until it returns true
+fn visit_redexes(term: &Term, found: impl FnMut(&[Dir]) -> bool) {
+    visit_redexes_from(vec![(term, 0, None)], Vec::new(), found)
+}
+
+/// Visits the subterms still to search in `work`, each with the length of its parent's path and
+/// the step from there, starting with the last. `path` leads to the parent of that last subterm.
+fn visit_redexes_from(mut work: Vec<(&Term, usize, Option<Dir>)>, mut path: Path, mut found: impl FnMut(&[Dir]) -> bool) {
+    while let Some((term, parent_length, dir)) = work.pop() {
+        path.truncate(parent_length);
+        path.extend(dir);
+        match term {
+            Term::Variable(_) => {},
+            Term::Abstraction(_, body) => {
+                work.push((body, path.len(), Some(Dir::Body)));
+            },
+            Term::Application(t1, t2) => {
+                if let Term::Abstraction(_, _) = **t1 {
+                    if found(&path) {
+                        return;
+                    }
+                }
+                work.push((t2, path.len(), Some(Dir::Arg)));
+                work.push((t1, path.len(), Some(Dir::Fun)));
+            },
+        }
+    }
+}
+
+/// Returns the subterm at the given path, or `None` if the path leads outside the term
+pub fn subterm_at<'t>(term: &'t Term, path: &[Dir]) -> Option<&'t Term> {
+    path.iter().try_fold(term, |term, dir| match (term, dir) {
+        (Term::Application(t1, _), Dir::Fun) => Some(&**t1),
+        (Term::Application(_, t2), Dir::Arg) => Some(&**t2),
+        (Term::Abstraction(_, body), Dir::Body) => Some(&**body),
+        _ => None,
+    })
+}
+
+/// Returns the subterm at the given path for modifying in place, or `None` if the path leads
+/// outside the term
+pub fn subterm_at_mut<'t>(term: &'t mut Term, path: &[Dir]) -> Option<&'t mut Term> {
+    path.iter().try_fold(term, |term, dir| match (term, dir) {
+        (Term::Application(t1, _), Dir::Fun) => Some(&mut **t1),
+        (Term::Application(_, t2), Dir::Arg) => Some(&mut **t2),
+        (Term::Abstraction(_, body), Dir::Body) => Some(&mut **body),
+        _ => None,
+    })
+}
+
+/// What's left of a node on a path once the subterm in the direction taken is removed
+enum Hole {
+    Fun(Term),
+    Arg(Term),
+    Body(String),
+}
+
+/// Puts the term back together around `result`, from the innermost hole out
+fn fill(mut holes: Vec<Hole>, mut result: Term) -> Term {
+    while let Some(hole) = holes.pop() {
+        result = match hole {
+            Hole::Fun(t2) => Term::Application(box result, box t2),
+            Hole::Arg(t1) => Term::Application(box t1, box result),
+            Hole::Body(name) => Term::Abstraction(name, box result),
+        };
+    }
+    result
+}
+
+/// Replaces the subterm at the given path with the result of calling `f` on it, or returns `None`
+/// if the path leads outside the term.
+///
+/// The term is taken apart along the path and put back together around the result, so nothing
+/// off the path is copied and deep terms don't overflow the stack.
+pub fn map_at(term: Term, path: &[Dir], f: impl FnOnce(Term) -> Term) -> Option<Term> {
+    let mut holes = Vec::with_capacity(path.len());
+    let mut focus = term;
+    for dir in path {
+        focus = match (dir, focus.into_parts()) {
+            (Dir::Fun, Parts::Application(t1, t2)) => {
+                holes.push(Hole::Fun(t2));
+                t1
+            },
+            (Dir::Arg, Parts::Application(t1, t2)) => {
+                holes.push(Hole::Arg(t1));
+                t2
+            },
+            (Dir::Body, Parts::Abstraction(name, body)) => {
+                holes.push(Hole::Body(name));
+                body
+            },
+            _ => return None,
+        };
+    }
+    Some(fill(holes, f(focus)))
+}
+
+/// Returns the term with the subterm at the given path replaced, or `None` if the path leads
+/// outside the term.
+///
+/// The replacement is inserted as is, so free variables in it may be captured by abstractions
+/// along the path. Only the subterms off the path are copied.
+pub fn replace_at(term: &Term, path: &[Dir], replacement: Term) -> Option<Term> {
+    let mut holes = Vec::with_capacity(path.len());
+    let mut focus = term;
+    for dir in path {
+        focus = match (dir, focus) {
+            (Dir::Fun, Term::Application(t1, t2)) => {
+                holes.push(Hole::Fun((**t2).clone()));
+                t1
+            },
+            (Dir::Arg, Term::Application(t1, t2)) => {
+                holes.push(Hole::Arg((**t1).clone()));
+                t2
+            },
+            (Dir::Body, Term::Abstraction(name, body)) => {
+                holes.push(Hole::Body(name.clone()));
+                body
+            },
+            _ => return None,
+        };
+    }
+    Some(fill(holes, replacement))
+}
+
+/// Contracts the redex at the given path, or returns `None` if there isn't a redex there
+pub fn contract_at(term: &Term, path: &[Dir]) -> Option<Term> {
+    match subterm_at(term, path)? {
+        Term::Application(box Term::Abstraction(name, body), arg) => {
+            replace_at(term, path, body.substitute(name.as_str(), arg))
+        },
+        _ => None,
+    }
+}
+
+/// Contracts the redex at the given path in place, or returns false if there isn't a redex there.
+///
+/// Only the body of the redex is copied, by substitution, and the rest of the term is untouched.
+pub fn contract_in_place(term: &mut Term, path: &[Dir]) -> bool {
+    let redex = match subterm_at_mut(term, path) {
+        Some(redex) => redex,
+        None => return false,
+    };
+    let result = match redex {
+        Term::Application(box Term::Abstraction(name, body), arg) => body.substitute(name.as_str(), arg),
+        _ => return false,
+    };
+    *redex = result;
+    true
+}
+
+/// Returns the path of the redex that was contracted to reduce `before` to `after` in one step,
+/// or `None` if no single contraction does so
+pub fn contracted_redex(before: &Term, after: &Term) -> Option<Path> {
+    redex_paths(before)
+        .into_iter()
+        .find(|path| contract_at(before, path).is_some_and(|t| t.is_alpha_equivalent(after)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Dir::*;
+    use crate::evaluation::{CallByValue, EmptyContext, Evaluable, Normal};
+    use crate::parser::parse_one;
+
+    fn parse(text: &str) -> Term {
+        parse_one(text).unwrap()
+    }
+
+    #[test]
+    fn test_lists_redex_paths_in_leftmost_outermost_order() {
+        assert_eq!(
+            vec![vec![], vec![Fun, Body], vec![Arg]],
+            redex_paths(&parse(r"(\x.(\y.y) x) ((\z.z) w)"))
+        );
+    }
+
+    #[test]
+    fn test_gets_subterm_at_path() {
+        let term = parse(r"(\x.x y) z");
+        assert_eq!(Some(&parse("y")), subterm_at(&term, &[Fun, Body, Arg]));
+        assert_eq!(Some(&term), subterm_at(&term, &[]));
+    }
+
+    #[test]
+    fn test_path_outside_term_has_no_subterm() {
+        assert_eq!(None, subterm_at(&parse(r"(\x.x y) z"), &[Arg, Body]));
+    }
+
+    #[test]
+    fn test_replaces_subterm_at_path() {
+        assert_eq!(
+            Some(parse(r"(\x.x w) z")),
+            replace_at(&parse(r"(\x.x y) z"), &[Fun, Body, Arg], parse("w"))
+        );
+    }
+
+    #[test]
+    fn test_finds_leftmost_outermost_redex() {
+        assert_eq!(Some(vec![Body, Arg]), leftmost_outermost_redex(&parse(r"\x.x ((\y.y) ((\z.z) x))")));
+        assert_eq!(None, leftmost_outermost_redex(&parse(r"\x.x (\y.y)")));
+    }
+
+    #[test]
+    fn test_maps_subterm_at_path() {
+        assert_eq!(
+            Some(parse(r"(\x.x (y w)) z")),
+            map_at(parse(r"(\x.x y) z"), &[Fun, Body, Arg], |t| Term::Application(box t, box parse("w")))
+        );
+        assert_eq!(None, map_at(parse(r"(\x.x y) z"), &[Arg, Body], |t| t));
+    }
+
+    #[test]
+    fn test_contracts_redex_at_path() {
+        assert_eq!(
+            Some(parse(r"(\x.x) ((\z.z) w)")),
+            contract_at(&parse(r"(\x.(\y.y) x) ((\z.z) w)"), &[Fun, Body])
+        );
+    }
+
+    #[test]
+    fn test_does_not_contract_at_path_without_redex() {
+        assert_eq!(None, contract_at(&parse(r"(\x.x) y"), &[Fun]));
+    }
+
+    #[test]
+    fn test_contracts_redex_in_place() {
+        let mut term = parse(r"(\x.(\y.y) x) ((\z.z) w)");
+        assert!(contract_in_place(&mut term, &[Fun, Body]));
+        assert_eq!(parse(r"(\x.x) ((\z.z) w)"), term);
+        assert!(!contract_in_place(&mut term, &[Fun]));
+        assert!(!contract_in_place(&mut term, &[Arg, Arg]));
+    }
+
+    #[test]
+    fn test_resumes_search_for_leftmost_outermost_redex() {
+        let terms = vec![
+            r"(\x.x) (\y.y) z",
+            r"(\x.x x) (\y.y)",
+            r"\a.a ((\x.x) b) ((\y.y) c) ((\z.z) d)",
+            r"(\m.\n.\s.\z.m s (n s z)) (\s.\z.s (s z)) (\s.\z.s z)",
+            r"(\x.y) ((\x.x x) (\x.x x))",
+        ];
+        for text in terms {
+            let mut term = parse(text);
+            let mut next = leftmost_outermost_redex(&term);
+            while let Some(path) = next {
+                assert!(contract_in_place(&mut term, &path));
+                next = next_leftmost_outermost_redex(&term, &path);
+                assert_eq!(leftmost_outermost_redex(&term), next, "after contracting {:?} in {}", path, text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_finds_redex_each_strategy_contracts() {
+        let term = parse(r"(\x.y) ((\z.z) \w.w)");
+        let normal = Normal::new().step(&mut EmptyContext{}, term.clone()).unwrap();
+        let call_by_value = CallByValue::new().step(&mut EmptyContext{}, term.clone()).unwrap();
+        assert_eq!(Some(vec![]), contracted_redex(&term, &normal));
+        assert_eq!(Some(vec![Arg]), contracted_redex(&term, &call_by_value));
+    }
+}
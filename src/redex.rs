@@ -0,0 +1,168 @@
+//! Redex enumeration and parallel reduction
+//!
+//! Redexes are numbered in leftmost outermost order, which is the order a pre-order traversal of
+//! the term visits them in, so redex 0 is the one the normal order strategy would contract.
+//!
+//! Parallel reduction contracts a set of redexes in one step. Redexes nested inside a chosen redex
+//! are contracted first, so the set is contracted simultaneously rather than one after another.
+//! Contracting every redex gives the complete development `t*`, which is the term that any
+//! parallel reduction of `t` can be completed to in a single further step (Takahashi's proof of
+//! confluence).
+use std::collections::HashSet;
+
+use crate::notation::named::Term;
+use crate::path::{contract_at, redex_paths, subterm_at, Dir, Path};
+use crate::substitution::Substitutable;
+
+/// Returns the redexes in the term, in leftmost outermost order
+pub fn redexes(term: &Term) -> Vec<&Term> {
+    redex_paths(term)
+        .iter()
+        .map(|path| subterm_at(term, path).expect("redex paths lead to subterms"))
+        .collect()
+}
+
+/// Contracts the redex with the given number, or returns `None` if the term has fewer redexes
+pub fn contract(term: &Term, redex: usize) -> Option<Term> {
+    redex_paths(term).get(redex).and_then(|path| contract_at(term, path))
+}
+
+/// Contracts all of the redexes with the given numbers simultaneously
+pub fn parallel_step(term: &Term, chosen: &HashSet<usize>) -> Term {
+    let chosen: HashSet<Path> = redex_paths(term)
+        .into_iter()
+        .enumerate()
+        .filter(|(redex, _)| chosen.contains(redex))
+        .map(|(_, path)| path)
+        .collect();
+    develop(term, &|path| chosen.contains(path))
+}
+
+/// Returns the complete development of the term, in which every redex is contracted
+pub fn complete_development(term: &Term) -> Term {
+    develop(term, &|_| true)
+}
+
+/// Contracts the redexes at the chosen paths, after contracting any chosen redexes inside them.
+///
+/// The term is developed from the leaves up with an explicit stack, so deep terms don't overflow.
+fn develop(term: &Term, chosen: &dyn Fn(&[Dir]) -> bool) -> Term {
+    enum Work<'t> {
+        Enter(&'t Term, usize, Option<Dir>),
+        ExitAbstraction(&'t str),
+        ExitApplication(&'t Term, usize),
+    }
+
+    let mut path = Vec::new();
+    let mut results = Vec::new();
+    let mut work = vec![Work::Enter(term, 0, None)];
+    while let Some(item) = work.pop() {
+        match item {
+            Work::Enter(term, parent_length, dir) => {
+                path.truncate(parent_length);
+                path.extend(dir);
+                match term {
+                    Term::Variable(_) => {
+                        results.push(term.clone());
+                    },
+                    Term::Abstraction(name, body) => {
+                        work.push(Work::ExitAbstraction(name));
+                        work.push(Work::Enter(body, path.len(), Some(Dir::Body)));
+                    },
+                    Term::Application(t1, t2) => {
+                        work.push(Work::ExitApplication(t1, path.len()));
+                        work.push(Work::Enter(t2, path.len(), Some(Dir::Arg)));
+                        work.push(Work::Enter(t1, path.len(), Some(Dir::Fun)));
+                    },
+                }
+            },
+            Work::ExitAbstraction(name) => {
+                let body = results.pop().expect("abstraction has a body");
+                results.push(Term::Abstraction(name.to_string(), box body));
+            },
+            Work::ExitApplication(t1, length) => {
+                path.truncate(length);
+                let t2_developed = results.pop().expect("application has an argument");
+                let t1_developed = results.pop().expect("application has a function");
+                results.push(match t1_developed {
+                    Term::Abstraction(ref name, ref body) if t1.is_value() && chosen(&path) => {
+                        body.substitute(name.as_str(), &t2_developed)
+                    },
+                    _ => Term::Application(box t1_developed, box t2_developed),
+                });
+            },
+        }
+    }
+    results.pop().expect("term has a result")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::{EmptyContext, Evaluable, Normal};
+    use crate::evaluation::testing::Random;
+    use crate::parser::parse_one;
+
+    fn parse(text: &str) -> Term {
+        parse_one(text).unwrap()
+    }
+
+    fn chosen(redexes: &[usize]) -> HashSet<usize> {
+        redexes.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_lists_redexes_in_leftmost_outermost_order() {
+        let term = parse(r"(\x.(\y.y) x) ((\z.z) w)");
+        let found: Vec<String> = redexes(&term).iter().map(|t| t.to_string()).collect();
+        assert_eq!(
+            vec![term.to_string(), parse(r"(\y.y) x").to_string(), parse(r"(\z.z) w").to_string()],
+            found
+        );
+    }
+
+    #[test]
+    fn test_lists_no_redexes_in_normal_form() {
+        assert!(redexes(&parse(r"\x.x (\y.y)")).is_empty());
+    }
+
+    #[test]
+    fn test_contracts_chosen_redex() {
+        let term = parse(r"(\x.x x) ((\z.z) w)");
+        assert_eq!(Some(parse(r"((\z.z) w) ((\z.z) w)")), contract(&term, 0));
+        assert_eq!(Some(parse(r"(\x.x x) w")), contract(&term, 1));
+        assert_eq!(None, contract(&term, 2));
+    }
+
+    #[test]
+    fn test_parallel_step_contracts_nested_redexes_simultaneously() {
+        let term = parse(r"(\x.x x) ((\z.z) w)");
+        assert_eq!(parse(r"w w"), parallel_step(&term, &chosen(&[0, 1])));
+    }
+
+    #[test]
+    fn test_parallel_step_with_no_redexes_chosen_is_identity() {
+        let term = parse(r"(\x.x x) ((\z.z) w)");
+        assert_eq!(term, parallel_step(&term, &chosen(&[])));
+    }
+
+    #[test]
+    fn test_complete_development_does_not_contract_created_redexes() {
+        assert_eq!(parse(r"(\y.y) z"), complete_development(&parse(r"(\x.x z) (\y.y)")));
+    }
+
+    #[test]
+    fn test_complete_development_of_omega_is_omega() {
+        let omega = parse(r"(\x.x x) (\x.x x)");
+        assert_eq!(omega, complete_development(&omega));
+    }
+
+    #[test]
+    fn test_first_redex_is_normal_order_redex() {
+        let mut random = Random::new(0x7a6a_4a51_0000_0034);
+        for _ in 0..500 {
+            let term = random.term(5, &mut Vec::new());
+            assert_eq!(Normal::new().step(&mut EmptyContext{}, term.clone()), contract(&term, 0));
+        }
+    }
+}
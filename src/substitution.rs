@@ -1,95 +1,153 @@
 //! Term substitution
+//!
+//! Substituting into an abstraction whose bound variable is free in the substitute would capture
+//! that variable, so the bound variable is renamed first. The new name comes from a `FreshNames`
+//! supply, which avoids every name that occurs in the abstraction or the substitute, and the
+//! candidates it tries come from a pluggable `NamingScheme`. The substituting itself is
+//! `generic::substitute_with`, the same as for every other notation; this module only supplies how
+//! named terms rename their binders.
+//!
+//! Each thread keeps a running count of the variables substituted and the abstractions renamed,
+//! which evaluation statistics are taken from.
 use std::borrow::Borrow;
+use std::cell::Cell;
+use std::collections::HashSet;
 
+use crate::notation::generic;
 use crate::notation::named::Term;
-use crate::vars::Variables;
 
-const PRIME: &str = "'";
+thread_local! {
+    static COUNTS: Cell<Counts> = Cell::new(Counts::default());
+}
+
+/// The work substitution has done on the current thread since it started
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Free occurrences of a variable replaced, including those replaced to rename a variable
+    pub substitutions: usize,
+
+    /// Bound variables renamed to avoid capture
+    pub renamings: usize,
+}
+
+/// Returns the work substitution has done on the current thread so far
+pub fn counts() -> Counts {
+    COUNTS.with(Cell::get)
+}
 
 /// An expression that can be substituted with another expression
 pub trait Substitutable {
-    /// Returns the free variables in a term.
+    /// Replaces the free occurrences of `var` with `substitution`, renaming bound variables with
+    /// primes where needed to avoid capture.
     fn substitute<T>(&self, var: T, substitution: &Term) -> Self
+        where T: Borrow<str>,
+              Self: Sized
+    {
+        self.substitute_with(var, substitution, &Primes)
+    }
+
+    /// Replaces the free occurrences of `var` with `substitution`, renaming bound variables with
+    /// names from `scheme` where needed to avoid capture.
+    fn substitute_with<T>(&self, var: T, substitution: &Term, scheme: &dyn NamingScheme) -> Self
         where T: Borrow<str>;
 }
 
 impl Substitutable for Term {
-    fn substitute<T>(&self, var: T, substitution: &Term) -> Self
+    fn substitute_with<T>(&self, var: T, substitution: &Term, scheme: &dyn NamingScheme) -> Self
         where T: Borrow<str>
     {
-        match self {
-            Term::Variable(s) => {
-                if s == var.borrow() {
-                    substitution.clone()
-                } else {
-                    self.clone()
-                }
-            },
-            Term::Abstraction(name, box t) => {
-                if name == var.borrow() {
-                    self.clone()
-                } else if substitution.free_variables().contains(name) {
-                    Term::Abstraction(
-                        name.clone() + PRIME,
-                        box rewrite(name, t).substitute(var.borrow(), substitution)
-                    )
-                } else {
-                    Term::Abstraction(name.clone(), box t.substitute(var.borrow(), substitution))
-                }
-            },
-            Term::Application(box t1, box t2) => {
-                Term::Application(
-                    box t1.substitute(var.borrow(), substitution),
-                    box t2.substitute(var.borrow(), substitution)
-                )
-            },
-        }
+        let mut counts = Counts::default();
+        let result = generic::substitute_with(
+            self,
+            &var.borrow().to_string(),
+            substitution,
+            &|name, body, free, counts| rename(name, body, free, scheme, counts),
+            &mut counts
+        );
+        COUNTS.with(|total| {
+            let before = total.get();
+            total.set(Counts {
+                substitutions: before.substitutions + counts.substitutions,
+                renamings: before.renamings + counts.renamings,
+            });
+        });
+        result
     }
 }
 
-struct Rewrite {
-    name: String,
-    count: usize,
+/// Renames the bound variable `name` of an abstraction with a name from `scheme`, if it's one of
+/// the `free` variables of a term about to be substituted into `body`, returning the new name and
+/// body. Replacing the occurrences of `name` in the body is added to `counts`.
+pub fn rename(name: &String, body: &Term, free: &HashSet<String>, scheme: &dyn NamingScheme, counts: &mut Counts) -> Option<(String, Term)> {
+    if !free.contains(name) {
+        return None;
+    }
+
+    let mut avoid = body.names();
+    avoid.extend(free.iter().cloned());
+    let new_name = FreshNames::new(scheme, avoid).fresh(name);
+    let body = generic::substitute_with(
+        body,
+        name,
+        &Term::Variable(new_name.clone()),
+        &|name, body, free, counts| rename(name, body, free, scheme, counts),
+        counts
+    );
+    Some((new_name, body))
 }
 
-// TODO this doesn't deal with "primed" names already being used as a bound variable name
-impl Rewrite {
-    fn rewrite(&mut self, term: &Term) -> Term {
-        match term {
-            Term::Variable(s) => {
-                if &self.name == s {
-                    Term::Variable(self.name.clone() + PRIME.repeat(self.count).as_str())
-                } else {
-                    term.clone()
-                }
-            },
-            Term::Abstraction(name, box t) => {
-                let new_name = if &self.name == name {
-                    self.count += 1;
-                    self.name.clone() + PRIME.repeat(self.count).as_str()
-                } else {
-                    name.clone()
-                };
-
-                Term::Abstraction(new_name, box self.rewrite(t))
-            },
-            Term::Application(box t1, box t2) => {
-                Term::Application(box self.rewrite(t1), box self.rewrite(t2))
-            },
-        }
+/// A way of generating alternatives to a variable name
+pub trait NamingScheme {
+    /// Returns the `n`th alternative to `name`, for `n` starting at 1. Different values of `n`
+    /// must give different names.
+    fn variant(&self, name: &str, n: usize) -> String;
+}
+
+/// Renames `x` to `x'`, `x''`, ...
+pub struct Primes;
+
+impl NamingScheme for Primes {
+    fn variant(&self, name: &str, n: usize) -> String {
+        name.to_string() + &"'".repeat(n)
     }
 }
 
-fn rewrite<T: Into<String>>(name: T, term: &Term) -> Term {
-    Rewrite {
-        name: name.into(),
-        count: 1
-    }.rewrite(term)
+/// Renames `x` to `x1`, `x2`, ..., replacing any numeric suffix `name` already has
+pub struct NumericSuffixes;
+
+impl NamingScheme for NumericSuffixes {
+    fn variant(&self, name: &str, n: usize) -> String {
+        format!("{}{}", name.trim_end_matches(|c: char| c.is_ascii_digit()), n)
+    }
+}
+
+/// A supply of names that are distinct from each other and from a set of names to avoid
+pub struct FreshNames<'s> {
+    scheme: &'s dyn NamingScheme,
+    avoid: HashSet<String>,
+}
+
+impl<'s> FreshNames<'s> {
+    pub fn new(scheme: &'s dyn NamingScheme, avoid: HashSet<String>) -> FreshNames<'s> {
+        FreshNames { scheme, avoid }
+    }
+
+    /// Returns the first variant of `name` that isn't avoided and hasn't been returned already
+    pub fn fresh(&mut self, name: &str) -> String {
+        let fresh = (1..)
+            .map(|n| self.scheme.variant(name, n))
+            .find(|candidate| !self.avoid.contains(candidate))
+            .unwrap();
+        self.avoid.insert(fresh.clone());
+        fresh
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::evaluation::testing::Random;
+    use crate::notation::conversion::remove_names;
     use crate::parser::parse_one;
 
     fn assert_substitutes_to(expected: &str, expr: &str, subs: &str, var: &str) {
@@ -99,6 +157,16 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_counts_substitutions_and_renamings() {
+        let before = counts();
+        parse_one(r"x (\y.x y) (\x.x)").unwrap().substitute("x", &parse_one("y").unwrap());
+        let after = counts();
+        // Renaming `y` replaces one occurrence of it, as well as the two of `x`
+        assert_eq!(3, after.substitutions - before.substitutions);
+        assert_eq!(1, after.renamings - before.renamings);
+    }
+
     #[test]
     fn test_variable_with_same_name() {
         assert_substitutes_to("y", "x", "y", "x");
@@ -140,49 +208,78 @@ mod tests {
     }
 
     #[test]
-    fn test_free_variable_replaced_in_abstraction_body() {
+    fn test_renamed_bound_variable_avoids_names_bound_inside() {
         assert_substitutes_to(
-            r"\b.x b",
-            r"\b.a b",
+            r"\x''.\x'.x'' x' x",
+            r"\x.\x'.x x' y",
             r"x",
-            "a"
+            "y"
         );
     }
 
-    mod rewrite {
-        use super::super::*;
-        use crate::parser::parse_one;
+    #[test]
+    fn test_renamed_bound_variable_avoids_free_names_in_body() {
+        assert_substitutes_to(
+            r"\x''.x'' x' x",
+            r"\x.x x' y",
+            r"x",
+            "y"
+        );
+    }
 
-        fn assert_rewrites_to(expected: &str, expr: &str, var: &str) {
-            assert_eq!(
-                parse_one(expected).unwrap(),
-                rewrite(var, &parse_one(expr).unwrap())
-            );
-        }
+    #[test]
+    fn test_does_not_rename_when_variable_not_free_in_body() {
+        assert_substitutes_to(r"\x.x", r"\x.x", r"x", "y");
+    }
 
-        #[test]
-        fn test_with_variable() {
-            assert_rewrites_to("x'", "x", "x");
-        }
+    #[test]
+    fn test_substitutes_with_numeric_suffixes() {
+        assert_eq!(
+            parse_one(r"\x2.x2 x1 x").unwrap(),
+            parse_one(r"\x.x x1 y").unwrap().substitute_with("y", &parse_one("x").unwrap(), &NumericSuffixes)
+        );
+    }
 
-        #[test]
-        fn test_with_application() {
-            assert_rewrites_to("x' y", "x y", "x");
-        }
+    #[test]
+    fn test_fresh_names_are_distinct() {
+        let avoid = vec!["x'".to_string()].into_iter().collect();
+        let mut names = FreshNames::new(&Primes, avoid);
+        assert_eq!("x''", names.fresh("x"));
+        assert_eq!("x'''", names.fresh("x"));
+    }
 
-        #[test]
-        fn test_simple_abstraction() {
-            assert_rewrites_to(r"\x''.x'' y", r"\x.x y", "x")
-        }
+    #[test]
+    fn test_named_substitution_agrees_with_de_bruijn_substitution() {
+        let free_variables = ["a", "b", "x", "x'", "y"];
+        let context: Vec<String> = free_variables.iter().map(|name| name.to_string()).collect();
+        let mut random = Random::new(0x5b57_0000_0000_0043);
+        for _ in 0..2000 {
+            let term = random.term_over(5, &mut free_variables.to_vec(), &["x", "x'", "x''", "y"]);
+            let substitution = random.term_over(3, &mut free_variables.to_vec(), &["x", "y"]);
+            let var = free_variables[random.next(free_variables.len())];
+            let index = (context.len() - 1 - context.iter().position(|name| name == var).unwrap()) as u32;
 
-        #[test]
-        fn test_abstraction_with_bound_name_in_free_variables_of_substitute() {
-            assert_rewrites_to(r"\x.x y'", r"\x.x y", "y");
+            let named = term.substitute(var, &substitution);
+            let expected = generic::substitute(
+                &remove_names(&term, &context).unwrap(),
+                &index,
+                &remove_names(&substitution, &context).unwrap()
+            );
+            assert_eq!(
+                expected,
+                remove_names(&named, &context).unwrap(),
+                "substituting {} for {} in {} gave {}", substitution, var, term, named
+            );
         }
+    }
 
-        #[test]
-        fn test_nested_abstraction_with_same_bound_variable_name() {
-            assert_rewrites_to(r"\x''.x'' \y.y \x'''.x'''", r"\x.x \y.y \x.x", "x");
-        }
+    #[test]
+    fn test_free_variable_replaced_in_abstraction_body() {
+        assert_substitutes_to(
+            r"\b.x b",
+            r"\b.a b",
+            r"x",
+            "a"
+        );
     }
 }
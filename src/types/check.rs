@@ -0,0 +1,333 @@
+//! Kind checking and type checking for System Fω (TAPL chapters 29 and 30)
+use std::{
+    collections::HashSet,
+};
+
+use failure::{
+    Fail,
+};
+
+use super::kind::Kind;
+use super::term::Term;
+use super::ty::{fresh_name, Type};
+
+#[derive(Debug, Fail)]
+pub enum TypeError {
+    #[fail(display = "unbound variable: {}", _0)]
+    UnboundVariable(String),
+
+    #[fail(display = "unbound type variable: {}", _0)]
+    UnboundTypeVariable(String),
+
+    #[fail(display = "expected {} to have kind {}, but it has kind {}", _0, _1, _2)]
+    KindMismatch(Type, Kind, Kind),
+
+    #[fail(display = "{} has kind {} and cannot be applied to a type", _0, _1)]
+    NotATypeOperator(Type, Kind),
+
+    #[fail(display = "{} has type {} and cannot be applied to a term", _0, _1)]
+    NotAFunction(Box<Term>, Type),
+
+    #[fail(display = "{} has type {} and cannot be applied to a type", _0, _1)]
+    NotPolymorphic(Box<Term>, Type),
+
+    #[fail(display = "expected argument of type {}, but found {}", _0, _1)]
+    TypeMismatch(Type, Type),
+}
+
+enum Binding {
+    Variable(String, Type),
+    TypeVariable(String, Kind),
+}
+
+/// The typing context, mapping term variables to their types and type variables to their kinds.
+pub struct Context {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Context {
+    fn default() -> Context {
+        Context::new()
+    }
+}
+
+impl Context {
+    pub fn new() -> Context {
+        Context {
+            bindings: Vec::new(),
+        }
+    }
+
+    fn type_of_variable(&self, name: &str) -> Result<Type, TypeError> {
+        self.bindings
+            .iter()
+            .rev()
+            .filter_map(|b| match b {
+                Binding::Variable(n, ty) if n == name => Some(ty.clone()),
+                _ => None,
+            })
+            .next()
+            .ok_or_else(|| TypeError::UnboundVariable(name.to_string()))
+    }
+
+    fn kind_of_variable(&self, name: &str) -> Result<Kind, TypeError> {
+        self.bindings
+            .iter()
+            .rev()
+            .filter_map(|b| match b {
+                Binding::TypeVariable(n, kind) if n == name => Some(kind.clone()),
+                _ => None,
+            })
+            .next()
+            .ok_or_else(|| TypeError::UnboundTypeVariable(name.to_string()))
+    }
+
+    fn binds_type_variable(&self, name: &str) -> bool {
+        self.kind_of_variable(name).is_ok()
+    }
+
+    /// Returns the kind of the given type
+    pub fn kind_of(&mut self, ty: &Type) -> Result<Kind, TypeError> {
+        match ty {
+            Type::Variable(name) => {
+                self.kind_of_variable(name)
+            },
+            Type::Arrow(t1, t2) => {
+                self.check_kind(t1, &Kind::Star)?;
+                self.check_kind(t2, &Kind::Star)?;
+                Ok(Kind::Star)
+            },
+            Type::ForAll(name, kind, t) => {
+                self.bindings.push(Binding::TypeVariable(name.clone(), kind.clone()));
+                let result = self.check_kind(t, &Kind::Star);
+                self.bindings.pop();
+                result.map(|_| Kind::Star)
+            },
+            Type::Abstraction(name, kind, t) => {
+                self.bindings.push(Binding::TypeVariable(name.clone(), kind.clone()));
+                let result = self.kind_of(t);
+                self.bindings.pop();
+                result.map(|body_kind| Kind::Arrow(box kind.clone(), box body_kind))
+            },
+            Type::Application(t1, t2) => {
+                match self.kind_of(t1)? {
+                    Kind::Arrow(box k11, box k12) => {
+                        self.check_kind(t2, &k11)?;
+                        Ok(k12)
+                    },
+                    k => Err(TypeError::NotATypeOperator(*t1.clone(), k)),
+                }
+            },
+        }
+    }
+
+    fn check_kind(&mut self, ty: &Type, expected: &Kind) -> Result<(), TypeError> {
+        let kind = self.kind_of(ty)?;
+        if &kind == expected {
+            Ok(())
+        } else {
+            Err(TypeError::KindMismatch(ty.clone(), expected.clone(), kind))
+        }
+    }
+
+    /// Returns the type of the given term
+    pub fn type_of(&mut self, term: &Term) -> Result<Type, TypeError> {
+        match term {
+            Term::Variable(name) => {
+                self.type_of_variable(name)
+            },
+            Term::Abstraction(name, ty, t) => {
+                self.check_kind(ty, &Kind::Star)?;
+                self.bindings.push(Binding::Variable(name.clone(), ty.clone()));
+                let result = self.type_of(t);
+                self.bindings.pop();
+                result.map(|body_type| Type::Arrow(box ty.clone(), box body_type))
+            },
+            Term::Application(t1, t2) => {
+                let ty1 = self.type_of(t1)?;
+                let ty2 = self.type_of(t2)?;
+                match ty1.normalize() {
+                    Type::Arrow(box ty11, box ty12) => {
+                        if ty11.is_equivalent(&ty2) {
+                            Ok(ty12)
+                        } else {
+                            Err(TypeError::TypeMismatch(ty11, ty2))
+                        }
+                    },
+                    _ => Err(TypeError::NotAFunction(t1.clone(), ty1)),
+                }
+            },
+            Term::TypeAbstraction(name, kind, t) => {
+                // Types already in the context may refer to an outer binding of `name`, so we
+                // rename the type variable to keep those references intact.
+                let (name, t) = if self.binds_type_variable(name) {
+                    let mut avoid = t.type_variable_names();
+                    avoid.extend(self.type_variable_names());
+                    let new_name = fresh_name(name, &avoid);
+                    let new_t = t.substitute_type(name.as_str(), &Type::Variable(new_name.clone()));
+                    (new_name, new_t)
+                } else {
+                    (name.clone(), *t.clone())
+                };
+
+                self.bindings.push(Binding::TypeVariable(name.clone(), kind.clone()));
+                let result = self.type_of(&t);
+                self.bindings.pop();
+                result.map(|body_type| Type::ForAll(name, kind.clone(), box body_type))
+            },
+            Term::TypeApplication(t, ty2) => {
+                let kind2 = self.kind_of(ty2)?;
+                let ty1 = self.type_of(t)?;
+                match ty1.normalize() {
+                    Type::ForAll(name, kind11, box ty12) => {
+                        if kind11 == kind2 {
+                            Ok(ty12.substitute(name.as_str(), ty2))
+                        } else {
+                            Err(TypeError::KindMismatch(ty2.clone(), kind11, kind2))
+                        }
+                    },
+                    _ => Err(TypeError::NotPolymorphic(t.clone(), ty1)),
+                }
+            },
+        }
+    }
+
+    fn type_variable_names(&self) -> HashSet<String> {
+        self.bindings
+            .iter()
+            .filter_map(|b| match b {
+                Binding::TypeVariable(n, _) => Some(n.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Returns the type of a closed term
+pub fn type_of(term: &Term) -> Result<Type, TypeError> {
+    Context::new().type_of(term)
+}
+
+/// Returns the kind of a closed type
+pub fn kind_of(ty: &Type) -> Result<Kind, TypeError> {
+    Context::new().kind_of(ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::parser::{parse, parse_one, parse_type, parse_kind};
+
+    fn assert_has_kind(expected: &str, ty: &str) {
+        assert_eq!(
+            parse_kind(expected).unwrap(),
+            kind_of(&parse_type(ty).unwrap()).unwrap()
+        );
+    }
+
+    fn assert_has_type(expected: &str, term: &str) {
+        let ty = type_of(&parse_one(term).unwrap()).unwrap();
+        assert!(
+            ty.is_equivalent(&parse_type(expected).unwrap()),
+            "expected {}, but found {}", expected, ty
+        );
+    }
+
+    fn assert_ill_typed(term: &str) {
+        assert!(type_of(&parse_one(term).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_arrow_has_kind_star() {
+        assert_has_kind("*", r"∀X.X→X");
+    }
+
+    #[test]
+    fn test_type_operator_has_arrow_kind() {
+        assert_has_kind("*⇒*⇒*", r"λX.λY.∀R.(X→Y→R)→R");
+    }
+
+    #[test]
+    fn test_higher_kinded_operator() {
+        assert_has_kind("(*⇒*)⇒*⇒*", r"λF::*⇒*.λX.F (F X)");
+    }
+
+    #[test]
+    fn test_applied_operator_has_result_kind() {
+        assert_has_kind("*", r"∀A.(λX.λY.X→Y) A A");
+    }
+
+    #[test]
+    fn test_ill_kinded_application() {
+        assert!(kind_of(&parse_type(r"∀A.∀B.A B").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_arrow_requires_proper_types() {
+        assert!(kind_of(&parse_type(r"∀A.(λX.X)→A").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_unbound_type_variable() {
+        assert!(kind_of(&parse_type(r"X").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_polymorphic_identity() {
+        assert_has_type(r"∀X.X→X", r"λX.λx:X.x");
+    }
+
+    #[test]
+    fn test_type_application() {
+        assert_has_type(r"∀Y.(Y→Y)→Y→Y", r"λY.(λX.λx:X.x) [Y→Y]");
+    }
+
+    #[test]
+    fn test_abstraction_annotated_with_operator_application() {
+        assert_has_type(r"∀A.(A→A)→A→A", r"λA.λf:(λX.X→X) A.f");
+    }
+
+    #[test]
+    fn test_application_up_to_equivalence() {
+        assert_has_type(r"∀A.A→A", r"λA.λx:A.(λy:(λX.X) A.y) x");
+    }
+
+    #[test]
+    fn test_shadowed_type_variable() {
+        assert_has_type(r"∀A.A→∀B.B→A", r"λA.λx:A.λA.λy:A.x");
+    }
+
+    #[test]
+    fn test_application_argument_mismatch() {
+        assert_ill_typed(r"λA.λB.λf:A→A.λb:B.f b");
+    }
+
+    #[test]
+    fn test_application_of_non_function() {
+        assert_ill_typed(r"λA.λx:A.x x");
+    }
+
+    #[test]
+    fn test_type_application_of_non_polymorphic_term() {
+        assert_ill_typed(r"λA.λx:A.x [A]");
+    }
+
+    #[test]
+    fn test_type_application_with_wrong_kind() {
+        assert_ill_typed(r"λA.(λF::*⇒*.λx:F A.x) [A]");
+    }
+
+    #[test]
+    fn test_pair_macros() {
+        let terms = parse(r"
+            Pair = λX.λY.∀R.(X→Y→R)→R;
+            pair = λX.λY.λx:X.λy:Y.λR.λf:X→Y→R.f x y;
+            fst = λX.λY.λp:Pair X Y.p [X] (λx:X.λy:Y.x);
+            λA.λB.λa:A.λb:B.fst [A] [B] (pair [A] [B] a b);
+            λA.λB.pair [A] [B];
+        ").unwrap();
+
+        assert!(type_of(&terms[0]).unwrap().is_equivalent(&parse_type(r"∀A.∀B.A→B→A").unwrap()));
+        assert!(type_of(&terms[1]).unwrap().is_equivalent(&parse_type(r"∀A.∀B.A→B→(λX.λY.∀R.(X→Y→R)→R) A B").unwrap()));
+    }
+}
@@ -0,0 +1,133 @@
+//! Type equivalence
+//!
+//! Two types are equivalent if their normal forms, obtained by reducing all type operator
+//! applications, are equal up to renaming of bound variables.
+use super::ty::Type;
+
+impl Type {
+    /// Returns the normal form of this type, reducing type operator applications in normal order
+    pub fn normalize(&self) -> Type {
+        match self {
+            Type::Variable(_) => {
+                self.clone()
+            },
+            Type::Arrow(t1, t2) => {
+                Type::Arrow(box t1.normalize(), box t2.normalize())
+            },
+            Type::ForAll(name, kind, t) => {
+                Type::ForAll(name.clone(), kind.clone(), box t.normalize())
+            },
+            Type::Abstraction(name, kind, t) => {
+                Type::Abstraction(name.clone(), kind.clone(), box t.normalize())
+            },
+            Type::Application(t1, t2) => {
+                match t1.normalize() {
+                    Type::Abstraction(name, _, body) => body.substitute(name.as_str(), t2).normalize(),
+                    t1 => Type::Application(box t1, box t2.normalize()),
+                }
+            },
+        }
+    }
+
+    /// Returns true if this type and `other` normalize to alpha-equivalent types
+    pub fn is_equivalent(&self, other: &Type) -> bool {
+        alpha_equivalent(&self.normalize(), &other.normalize(), &mut Vec::new(), &mut Vec::new())
+    }
+}
+
+/// Compares two types structurally, treating bound variables as equal if they were bound at the
+/// same depth.
+fn alpha_equivalent<'t>(t1: &'t Type, t2: &'t Type, names1: &mut Vec<&'t str>, names2: &mut Vec<&'t str>) -> bool {
+    match (t1, t2) {
+        (Type::Variable(x1), Type::Variable(x2)) => {
+            let p1 = names1.iter().rposition(|n| n == x1);
+            let p2 = names2.iter().rposition(|n| n == x2);
+            match (p1, p2) {
+                (None, None) => x1 == x2,
+                (p1, p2) => p1 == p2,
+            }
+        },
+        (Type::Arrow(a1, b1), Type::Arrow(a2, b2)) |
+        (Type::Application(a1, b1), Type::Application(a2, b2)) => {
+            alpha_equivalent(a1, a2, names1, names2) && alpha_equivalent(b1, b2, names1, names2)
+        },
+        (Type::ForAll(x1, k1, b1), Type::ForAll(x2, k2, b2)) |
+        (Type::Abstraction(x1, k1, b1), Type::Abstraction(x2, k2, b2)) => {
+            if k1 != k2 {
+                return false;
+            }
+
+            names1.push(x1.as_str());
+            names2.push(x2.as_str());
+            let result = alpha_equivalent(b1, b2, names1, names2);
+            names1.pop();
+            names2.pop();
+            result
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::parser::parse_type;
+
+    fn assert_equivalent(t1: &str, t2: &str) {
+        assert!(
+            parse_type(t1).unwrap().is_equivalent(&parse_type(t2).unwrap()),
+            "{} ≡ {}", t1, t2
+        );
+    }
+
+    fn assert_not_equivalent(t1: &str, t2: &str) {
+        assert!(
+            !parse_type(t1).unwrap().is_equivalent(&parse_type(t2).unwrap()),
+            "{} ≢ {}", t1, t2
+        );
+    }
+
+    #[test]
+    fn test_variable_is_equivalent_to_itself() {
+        assert_equivalent("X", "X");
+    }
+
+    #[test]
+    fn test_different_free_variables_are_not_equivalent() {
+        assert_not_equivalent("X", "Y");
+    }
+
+    #[test]
+    fn test_alpha_equivalent_types_are_equivalent() {
+        assert_equivalent(r"∀X.X→X", r"∀Y.Y→Y");
+    }
+
+    #[test]
+    fn test_bound_and_free_variables_are_not_equivalent() {
+        assert_not_equivalent(r"∀X.X→Y", r"∀Y.Y→Y");
+    }
+
+    #[test]
+    fn test_kinds_must_match() {
+        assert_not_equivalent(r"∀X.X", r"∀X::*⇒*.X");
+    }
+
+    #[test]
+    fn test_operator_application_is_equivalent_to_its_reduction() {
+        assert_equivalent(r"(λX.X→X) Y", r"Y→Y");
+    }
+
+    #[test]
+    fn test_operator_application_under_binder() {
+        assert_equivalent(r"∀Y.(λX.X→X) Y", r"∀Z.Z→Z");
+    }
+
+    #[test]
+    fn test_higher_kinded_operator_application() {
+        assert_equivalent(r"(λF::*⇒*.λX.F (F X)) (λY.Y→Y) Z", r"(Z→Z)→Z→Z");
+    }
+
+    #[test]
+    fn test_pair_encoding() {
+        assert_equivalent(r"(λX.λY.∀R.(X→Y→R)→R) A B", r"∀S.(A→B→S)→S");
+    }
+}
@@ -0,0 +1,37 @@
+//! Kinds, which classify types in the same way that types classify terms.
+use std::{
+    fmt,
+};
+
+#[derive(Clone, PartialEq)]
+pub enum Kind {
+    /// The kind of proper types, such as `X → X`
+    Star,
+
+    /// The kind of type operators, such as `λX::*.X → X`
+    Arrow(Box<Kind>, Box<Kind>),
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Star => write!(f, "*"),
+            Kind::Arrow(k1 @ box Kind::Arrow(_, _), k2) => write!(f, "({}) ⇒ {}", k1, k2),
+            Kind::Arrow(k1, k2) => write!(f, "{} ⇒ {}", k1, k2),
+        }
+    }
+}
+
+impl fmt::Debug for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+pub fn star() -> Kind {
+    Kind::Star
+}
+
+pub fn k(k1: Kind, k2: Kind) -> Kind {
+    Kind::Arrow(Box::new(k1), Box::new(k2))
+}
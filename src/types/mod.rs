@@ -0,0 +1,17 @@
+//! System Fω: the polymorphic lambda calculus extended with type operators.
+//!
+//! Types can be abstracted over and applied at the type level (`λX::K.T` and `T T`), and are
+//! classified by kinds (`*` and `K ⇒ K`). Types are compared by reducing them to a normal form
+//! and checking for alpha-equivalence.
+
+pub mod check;
+pub mod equivalence;
+pub mod kind;
+pub mod parser;
+pub mod term;
+pub mod ty;
+
+pub use check::{kind_of, type_of, Context, TypeError};
+pub use kind::Kind;
+pub use term::Term;
+pub use ty::Type;
@@ -0,0 +1,355 @@
+//! Parser for System Fω programs
+//!
+//! The syntax extends the untyped syntax with type annotations (`\x:T. t`), type abstractions
+//! (`\X::K. t`, where a missing kind is `*`), type applications (`t [T]`) and type macros
+//! (`Pair = \X.\Y.∀R.(X→Y→R)→R`). Type-level names start with an uppercase letter.
+use std::{
+    collections::HashMap,
+};
+
+use pest::{
+    Parser as PestParser,
+    iterators::{Pair, Pairs},
+};
+
+use failure::{
+    Fail,
+};
+
+use super::kind::Kind;
+use super::term::Term;
+use super::ty::Type;
+
+#[derive(pest_derive::Parser)]
+#[grammar = "types/grammar.pest"]
+struct SystemFOmegaParser;
+
+pub struct Parser<'p> {
+    macros: HashMap<&'p str, Term>,
+    type_macros: HashMap<&'p str, Type>,
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Fail)]
+enum ParseError {
+    #[fail(display = "empty input")]
+    EmptyInput,
+
+    #[fail(display = "no terms")]
+    NoTerms,
+
+    #[fail(display = "failed to parse: {}", _0)]
+    Syntax(String),
+
+    #[fail(display = "parse error for pair: {}", _0)]
+    Unknown(&'static str),
+}
+
+pub fn parse(text: &str) -> Result<Vec<Term>, failure::Error> {
+    let mut parser = Parser::new();
+    parser.parse(text).map(Iterator::collect)
+}
+
+pub fn parse_one(text: &str) -> Result<Term, failure::Error> {
+    let mut parser = Parser::new();
+    parser.parse(text).and_then(|mut iter| iter.next().ok_or_else(|| ParseError::NoTerms.into()))
+}
+
+pub fn parse_type(text: &str) -> Result<Type, failure::Error> {
+    let pair = SystemFOmegaParser::parse(Rule::type_, text)
+        .map_err(|e| ParseError::Syntax(e.to_string()))
+        .map(|mut pairs| pairs.next())?
+        .ok_or(ParseError::EmptyInput)?;
+
+    Ok(Parser::new().process_type(pair)?)
+}
+
+pub fn parse_kind(text: &str) -> Result<Kind, failure::Error> {
+    let pair = SystemFOmegaParser::parse(Rule::kind, text)
+        .map_err(|e| ParseError::Syntax(e.to_string()))
+        .map(|mut pairs| pairs.next())?
+        .ok_or(ParseError::EmptyInput)?;
+
+    Ok(process_kind(pair)?)
+}
+
+fn process_kind(pair: Pair<'_, Rule>) -> Result<Kind, ParseError> {
+    match pair.as_rule() {
+        Rule::kind | Rule::simple_kind => {
+            process_kind(pair.into_inner().next().ok_or(ParseError::Unknown("kind"))?)
+        },
+        Rule::star => {
+            Ok(Kind::Star)
+        },
+        Rule::kind_arrow => {
+            let mut pairs = pair.into_inner();
+            let k1 = pairs.next().ok_or(ParseError::Unknown("kind_arrow[0]")).map(process_kind)??;
+            let k2 = pairs.next().ok_or(ParseError::Unknown("kind_arrow[1]")).map(process_kind)??;
+            Ok(Kind::Arrow(box k1, box k2))
+        },
+        rule => unreachable!("{:?}", rule),
+    }
+}
+
+/// Reads the name, kind (defaulting to `*`) and body from the pairs of a binder
+fn process_binder<'p>(mut pairs: Pairs<'p, Rule>) -> Result<(String, Kind, Pair<'p, Rule>), ParseError> {
+    let name = pairs.next().map(|p| p.as_str().to_string()).ok_or(ParseError::Unknown("binder name"))?;
+    let next = pairs.next().ok_or(ParseError::Unknown("binder body"))?;
+    if next.as_rule() == Rule::kind {
+        let body = pairs.next().ok_or(ParseError::Unknown("binder body"))?;
+        Ok((name, process_kind(next)?, body))
+    } else {
+        Ok((name, Kind::Star, next))
+    }
+}
+
+impl <'p> Default for Parser<'p> {
+    fn default() -> Parser<'p> {
+        Parser::new()
+    }
+}
+
+impl <'p> Parser<'p> {
+    pub fn new() -> Parser<'p> {
+        Parser {
+            macros: HashMap::new(),
+            type_macros: HashMap::new(),
+            terms: Vec::new(),
+        }
+    }
+
+    pub fn parse(&mut self, text: &'p str) -> Result<impl Iterator<Item=Term> + '_, failure::Error> {
+        let pair = SystemFOmegaParser::parse(Rule::main, text)
+            .map_err(|e| ParseError::Syntax(e.to_string()))
+            .map(|mut pairs| pairs.next())?
+            .ok_or(ParseError::EmptyInput)?;
+
+        self.process_pair(pair)?;
+        Ok(self.terms.drain(0..))
+    }
+
+    fn process_pair(&mut self, pair: Pair<'p, Rule>) -> Result<(), ParseError> {
+        match pair.as_rule() {
+            Rule::main  => {
+                pair.into_inner().try_for_each(|p| self.process_pair(p))
+            },
+            Rule::macro_ => {
+                self.read_macro(pair)
+            },
+            Rule::type_macro => {
+                self.read_type_macro(pair)
+            },
+            Rule::term => {
+                let term = self.process_term(pair)?;
+                self.terms.push(term);
+                Ok(())
+            },
+            Rule::EOI => {
+                Ok(())
+            },
+            rule => unreachable!("{:?}", rule),
+        }
+    }
+
+    fn process_term(&mut self, pair: Pair<'p, Rule>) -> Result<Term, ParseError> {
+        match pair.as_rule() {
+            Rule::term | Rule::simple_term => {
+                self.process_term(pair.into_inner().next().ok_or(ParseError::Unknown("term"))?)
+            },
+            Rule::variable => {
+                let name = pair.as_str();
+                Ok(
+                    self.macros.get(name)
+                               .cloned()
+                               .unwrap_or_else(|| Term::Variable(name.to_string()))
+                )
+            },
+            Rule::abstraction => {
+                let mut pairs = pair.into_inner();
+                let name = pairs.next().map(|p| p.as_str().to_string()).ok_or(ParseError::Unknown("abstraction[0]"))?;
+                let ty = pairs.next().ok_or(ParseError::Unknown("abstraction[1]")).map(|t| self.process_type(t))??;
+                let body = pairs.next().ok_or(ParseError::Unknown("abstraction[2]")).map(|t| self.process_term(t))??;
+                Ok(Term::Abstraction(name, ty, box body))
+            },
+            Rule::type_abstraction_term => {
+                let (name, kind, body) = process_binder(pair.into_inner())?;
+                Ok(Term::TypeAbstraction(name, kind, box self.process_term(body)?))
+            },
+            Rule::application => {
+                let mut pairs = pair.into_inner();
+                let t1 =
+                        pairs.next()
+                             .ok_or(ParseError::Unknown("application[0]"))
+                             .map(|t| self.process_term(t))??;
+
+                pairs.try_fold(t1, |app, t| {
+                    if t.as_rule() == Rule::type_argument {
+                        let ty = self.process_type(t)?;
+                        Ok(Term::TypeApplication(box app, ty))
+                    } else {
+                        let term = self.process_term(t)?;
+                        Ok(Term::Application(box app, box term))
+                    }
+                })
+            },
+            rule => unreachable!("{:?}", rule),
+        }
+    }
+
+    fn process_type(&mut self, pair: Pair<'p, Rule>) -> Result<Type, ParseError> {
+        match pair.as_rule() {
+            Rule::type_ | Rule::simple_type | Rule::type_argument => {
+                self.process_type(pair.into_inner().next().ok_or(ParseError::Unknown("type"))?)
+            },
+            Rule::type_variable => {
+                let name = pair.as_str();
+                Ok(
+                    self.type_macros.get(name)
+                                    .cloned()
+                                    .unwrap_or_else(|| Type::Variable(name.to_string()))
+                )
+            },
+            Rule::type_forall => {
+                let (name, kind, body) = process_binder(pair.into_inner())?;
+                Ok(Type::ForAll(name, kind, box self.process_type(body)?))
+            },
+            Rule::type_abstraction => {
+                let (name, kind, body) = process_binder(pair.into_inner())?;
+                Ok(Type::Abstraction(name, kind, box self.process_type(body)?))
+            },
+            Rule::type_arrow => {
+                let mut pairs = pair.into_inner();
+                let t1 = pairs.next().ok_or(ParseError::Unknown("type_arrow[0]")).map(|t| self.process_type(t))??;
+                let t2 = pairs.next().ok_or(ParseError::Unknown("type_arrow[1]")).map(|t| self.process_type(t))??;
+                Ok(Type::Arrow(box t1, box t2))
+            },
+            Rule::type_application => {
+                let mut pairs = pair.into_inner();
+                let t1 =
+                        pairs.next()
+                             .ok_or(ParseError::Unknown("type_application[0]"))
+                             .map(|t| self.process_type(t))??;
+
+                pairs.try_fold(t1, |app, t| {
+                    let ty = self.process_type(t)?;
+                    Ok(Type::Application(box app, box ty))
+                })
+            },
+            rule => unreachable!("{:?}", rule),
+        }
+    }
+
+    fn read_macro(&mut self, pair: Pair<'p, Rule>) -> Result<(), ParseError> {
+        let mut pairs = pair.into_inner();
+        pairs.next()
+             .ok_or(ParseError::Unknown("macro name"))
+             .map(|t| t.as_str())
+             .and_then(|name| {
+                 let macro_body = self.process_term(pairs.next().ok_or(ParseError::Unknown("macro body"))?)?;
+                 self.macros.insert(name, macro_body);
+                 Ok(())
+             })
+    }
+
+    fn read_type_macro(&mut self, pair: Pair<'p, Rule>) -> Result<(), ParseError> {
+        let mut pairs = pair.into_inner();
+        pairs.next()
+             .ok_or(ParseError::Unknown("type macro name"))
+             .map(|t| t.as_str())
+             .and_then(|name| {
+                 let macro_body = self.process_type(pairs.next().ok_or(ParseError::Unknown("type macro body"))?)?;
+                 self.type_macros.insert(name, macro_body);
+                 Ok(())
+             })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::kind::{star, k};
+    use crate::types::ty::{tv, arrow, forall, tl, ta};
+
+    #[test]
+    fn test_parses_kind_arrows_right_associatively() {
+        assert_eq!(
+            k(star(), k(star(), star())),
+            parse_kind("* ⇒ * => *").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parses_kind_with_parentheses() {
+        assert_eq!(
+            k(k(star(), star()), star()),
+            parse_kind("(* ⇒ *) ⇒ *").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parses_arrows_right_associatively() {
+        assert_eq!(
+            arrow("X", arrow("Y", "Z")),
+            parse_type("X → Y -> Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parses_type_application_tighter_than_arrow() {
+        assert_eq!(
+            arrow(ta(ta("F", "X"), "Y"), ta("G", "Z")),
+            parse_type("F X Y → G Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parses_forall_with_default_kind() {
+        assert_eq!(
+            forall("X", star(), arrow("X", "X")),
+            parse_type("∀X. X → X").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parses_type_abstraction_with_kind() {
+        assert_eq!(
+            tl("F", k(star(), star()), ta("F", "X")),
+            parse_type(r"\F::*⇒*. F X").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parses_typed_abstraction() {
+        assert_eq!(
+            Term::Abstraction("x".to_string(), arrow("X", "X"), box Term::Variable("x".to_string())),
+            parse_one(r"\x:X→X. x").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parses_type_abstraction_and_application() {
+        assert_eq!(
+            Term::TypeAbstraction(
+                "X".to_string(),
+                star(),
+                box Term::Application(
+                    box Term::TypeApplication(box Term::Variable("f".to_string()), tv("X")),
+                    box Term::Variable("x".to_string())
+                )
+            ),
+            parse_one(r"\X. f [X] x").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parses_and_substitutes_type_macro() {
+        assert_eq!(
+            Term::Abstraction(
+                "p".to_string(),
+                ta(ta(tl("X", star(), tl("Y", star(), arrow("X", "Y"))), "A"), "B"),
+                box Term::Variable("p".to_string())
+            ),
+            parse_one(r"Fn = \X.\Y.X→Y; \p:Fn A B. p").unwrap()
+        );
+    }
+}
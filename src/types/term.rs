@@ -0,0 +1,126 @@
+//! Terms for System Fω
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt,
+};
+
+use crate::notation::named;
+use crate::vars::Variables;
+
+use super::kind::Kind;
+use super::ty::Type;
+
+#[derive(Clone, PartialEq)]
+pub enum Term {
+    Variable(String),
+    Abstraction(String, Type, Box<Term>),
+    Application(Box<Term>, Box<Term>),
+    TypeAbstraction(String, Kind, Box<Term>),
+    TypeApplication(Box<Term>, Type),
+}
+
+impl Term {
+    /// Returns the untyped term that remains after removing all type annotations, type
+    /// abstractions and type applications.
+    pub fn erase(&self) -> named::Term {
+        match self {
+            Term::Variable(s) => named::Term::Variable(s.clone()),
+            Term::Abstraction(s, _, t) => named::Term::Abstraction(s.clone(), box t.erase()),
+            Term::Application(t1, t2) => named::Term::Application(box t1.erase(), box t2.erase()),
+            Term::TypeAbstraction(_, _, t) => t.erase(),
+            Term::TypeApplication(t, _) => t.erase(),
+        }
+    }
+
+    /// Returns the type variable names that this term binds or references in its annotations
+    pub fn type_variable_names(&self) -> HashSet<String> {
+        match self {
+            Term::Variable(_) => {
+                HashSet::new()
+            },
+            Term::Abstraction(_, ty, t) => {
+                let mut names = ty.free_variables();
+                names.extend(t.type_variable_names());
+                names
+            },
+            Term::Application(t1, t2) => {
+                let mut names = t1.type_variable_names();
+                names.extend(t2.type_variable_names());
+                names
+            },
+            Term::TypeAbstraction(name, _, t) => {
+                let mut names = t.type_variable_names();
+                names.insert(name.clone());
+                names
+            },
+            Term::TypeApplication(t, ty) => {
+                let mut names = t.type_variable_names();
+                names.extend(ty.free_variables());
+                names
+            },
+        }
+    }
+
+    /// Substitutes `substitution` for the type variable `var` in all type annotations.
+    ///
+    /// Type abstractions in this term are not renamed, so the caller must ensure that none of
+    /// them bind a free variable of `substitution`.
+    pub fn substitute_type<T>(&self, var: T, substitution: &Type) -> Term
+        where T: Borrow<str>
+    {
+        match self {
+            Term::Variable(_) => {
+                self.clone()
+            },
+            Term::Abstraction(name, ty, box t) => {
+                Term::Abstraction(
+                    name.clone(),
+                    ty.substitute(var.borrow(), substitution),
+                    box t.substitute_type(var.borrow(), substitution)
+                )
+            },
+            Term::Application(box t1, box t2) => {
+                Term::Application(
+                    box t1.substitute_type(var.borrow(), substitution),
+                    box t2.substitute_type(var.borrow(), substitution)
+                )
+            },
+            Term::TypeAbstraction(name, _, _) if name == var.borrow() => {
+                self.clone()
+            },
+            Term::TypeAbstraction(name, kind, box t) => {
+                Term::TypeAbstraction(name.clone(), kind.clone(), box t.substitute_type(var.borrow(), substitution))
+            },
+            Term::TypeApplication(box t, ty) => {
+                Term::TypeApplication(
+                    box t.substitute_type(var.borrow(), substitution),
+                    ty.substitute(var.borrow(), substitution)
+                )
+            },
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Variable(s) => write!(f, "{}", s),
+            Term::Abstraction(s, ty, t) => write!(f, "λ{}:{}.{}", s, ty, t),
+            Term::TypeAbstraction(s, Kind::Star, t) => write!(f, "λ{}.{}", s, t),
+            Term::TypeAbstraction(s, k, t) => write!(f, "λ{}::{}.{}", s, k, t),
+            Term::TypeApplication(t @ box Term::Variable(_), ty) => write!(f, "{} [{}]", t, ty),
+            Term::TypeApplication(t, ty) => write!(f, "({}) [{}]", t, ty),
+            Term::Application(box Term::Variable(t1), box Term::Variable(t2)) => write!(f, "{} {}", t1, t2),
+            Term::Application(box Term::Variable(t1), t2) => write!(f, "{} ({})", t1, t2),
+            Term::Application(t1, box Term::Variable(t2)) => write!(f, "({}) {}", t1, t2),
+            Term::Application(t1, t2) => write!(f, "({}) ({})", t1, t2),
+        }
+    }
+}
+
+impl fmt::Debug for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
@@ -0,0 +1,235 @@
+//! Types for System Fω, including type-level abstraction and application (type operators).
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt,
+};
+
+use crate::vars::Variables;
+
+use super::kind::Kind;
+
+const PRIME: &str = "'";
+
+#[derive(Clone, PartialEq)]
+pub enum Type {
+    Variable(String),
+    Arrow(Box<Type>, Box<Type>),
+    ForAll(String, Kind, Box<Type>),
+    Abstraction(String, Kind, Box<Type>),
+    Application(Box<Type>, Box<Type>),
+}
+
+impl Type {
+    /// Returns true if this type can be reduced by applying a type operator
+    pub fn is_redex(&self) -> bool {
+        match self {
+            Type::Application(box Type::Abstraction(_, _, _), _) => true,
+            Type::Application(t1, t2) | Type::Arrow(t1, t2) => t1.is_redex() || t2.is_redex(),
+            Type::ForAll(_, _, t) | Type::Abstraction(_, _, t) => t.is_redex(),
+            _ => false,
+        }
+    }
+
+    /// Capture-avoiding substitution of `substitution` for the type variable `var`
+    pub fn substitute<T>(&self, var: T, substitution: &Type) -> Type
+        where T: Borrow<str>
+    {
+        match self {
+            Type::Variable(s) => {
+                if s == var.borrow() {
+                    substitution.clone()
+                } else {
+                    self.clone()
+                }
+            },
+            Type::Arrow(box t1, box t2) => {
+                Type::Arrow(
+                    box t1.substitute(var.borrow(), substitution),
+                    box t2.substitute(var.borrow(), substitution)
+                )
+            },
+            Type::Application(box t1, box t2) => {
+                Type::Application(
+                    box t1.substitute(var.borrow(), substitution),
+                    box t2.substitute(var.borrow(), substitution)
+                )
+            },
+            Type::ForAll(name, kind, box t) => {
+                let (name, t) = substitute_under_binder(name, t, var.borrow(), substitution);
+                Type::ForAll(name, kind.clone(), box t)
+            },
+            Type::Abstraction(name, kind, box t) => {
+                let (name, t) = substitute_under_binder(name, t, var.borrow(), substitution);
+                Type::Abstraction(name, kind.clone(), box t)
+            },
+        }
+    }
+}
+
+/// Substitutes into the body of a binder, renaming the bound variable if it would capture one of
+/// the free variables in `substitution`.
+fn substitute_under_binder(name: &str, body: &Type, var: &str, substitution: &Type) -> (String, Type) {
+    if name == var {
+        return (name.to_string(), body.clone());
+    }
+
+    let substitution_fvs = substitution.free_variables();
+    if !substitution_fvs.contains(name) {
+        return (name.to_string(), body.substitute(var, substitution));
+    }
+
+    let mut avoid = body.free_variables();
+    avoid.extend(substitution_fvs);
+    avoid.insert(var.to_string());
+
+    let new_name = fresh_name(name, &avoid);
+    let body = body.substitute(name, &Type::Variable(new_name.clone()));
+    (new_name, body.substitute(var, substitution))
+}
+
+/// Returns `name` with enough primes appended that it doesn't collide with anything in `avoid`
+pub fn fresh_name(name: &str, avoid: &HashSet<String>) -> String {
+    let mut new_name = name.to_string() + PRIME;
+    while avoid.contains(&new_name) {
+        new_name += PRIME;
+    }
+    new_name
+}
+
+impl Variables for Type {
+    type Name = String;
+
+    fn free_variables(&self) -> HashSet<String> {
+        match self {
+            Type::Variable(s) => {
+                vec![s.clone()].into_iter().collect()
+            },
+            Type::ForAll(s, _, t) | Type::Abstraction(s, _, t) => {
+                let mut fv = t.free_variables();
+                fv.remove(s);
+                fv
+            },
+            Type::Arrow(t1, t2) | Type::Application(t1, t2) => {
+                let mut fv = t1.free_variables();
+                fv.extend(t2.free_variables().into_iter());
+                fv
+            },
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Variable(s) => write!(f, "{}", s),
+            Type::ForAll(s, Kind::Star, t) => write!(f, "∀{}.{}", s, t),
+            Type::ForAll(s, k, t) => write!(f, "∀{}::{}.{}", s, k, t),
+            Type::Abstraction(s, Kind::Star, t) => write!(f, "λ{}.{}", s, t),
+            Type::Abstraction(s, k, t) => write!(f, "λ{}::{}.{}", s, k, t),
+            Type::Arrow(t1 @ box Type::Variable(_), t2) => write!(f, "{}→{}", t1, t2),
+            Type::Arrow(t1 @ box Type::Application(_, _), t2) => write!(f, "{}→{}", t1, t2),
+            Type::Arrow(t1, t2) => write!(f, "({})→{}", t1, t2),
+            Type::Application(t1, t2 @ box Type::Variable(_)) => match t1 {
+                box Type::Variable(_) | box Type::Application(_, _) => write!(f, "{} {}", t1, t2),
+                _ => write!(f, "({}) {}", t1, t2),
+            },
+            Type::Application(t1, t2) => match t1 {
+                box Type::Variable(_) | box Type::Application(_, _) => write!(f, "{} ({})", t1, t2),
+                _ => write!(f, "({}) ({})", t1, t2),
+            },
+        }
+    }
+}
+
+impl fmt::Debug for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+pub fn tv<S: Into<String>>(name: S) -> Type {
+    Type::Variable(name.into())
+}
+
+pub fn arrow<T1, T2>(t1: T1, t2: T2) -> Type
+    where T1: Into<Type>,
+          T2: Into<Type>
+{
+    Type::Arrow(Box::new(t1.into()), Box::new(t2.into()))
+}
+
+pub fn forall<S, T>(name: S, kind: Kind, body: T) -> Type
+    where S: Into<String>,
+          T: Into<Type>
+{
+    Type::ForAll(name.into(), kind, Box::new(body.into()))
+}
+
+pub fn tl<S, T>(name: S, kind: Kind, body: T) -> Type
+    where S: Into<String>,
+          T: Into<Type>
+{
+    Type::Abstraction(name.into(), kind, Box::new(body.into()))
+}
+
+pub fn ta<T1, T2>(t1: T1, t2: T2) -> Type
+    where T1: Into<Type>,
+          T2: Into<Type>
+{
+    Type::Application(Box::new(t1.into()), Box::new(t2.into()))
+}
+
+impl <T> From<T> for Type
+    where T: Into<String>
+{
+    fn from(s: T) -> Type {
+        Type::Variable(s.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::kind::star;
+
+    #[test]
+    fn test_substitutes_free_variable() {
+        assert_eq!(
+            arrow("Y", "Y"),
+            arrow("X", "Y").substitute("X", &tv("Y"))
+        );
+    }
+
+    #[test]
+    fn test_does_not_substitute_bound_variable() {
+        assert_eq!(
+            forall("X", star(), arrow("X", "X")),
+            forall("X", star(), arrow("X", "X")).substitute("X", &tv("Y"))
+        );
+    }
+
+    #[test]
+    fn test_substitution_avoids_capture() {
+        assert_eq!(
+            forall("Y'", star(), arrow("Y", "Y'")),
+            forall("Y", star(), arrow("X", "Y")).substitute("X", &tv("Y"))
+        );
+    }
+
+    #[test]
+    fn test_substitution_avoids_capture_with_existing_primed_name() {
+        assert_eq!(
+            tl("Y''", star(), ta(ta("Y", "Y'"), "Y''")),
+            tl("Y", star(), ta(ta("X", "Y'"), "Y")).substitute("X", &tv("Y"))
+        );
+    }
+
+    #[test]
+    fn test_displays_arrows_right_associatively() {
+        assert_eq!(
+            "(X→Y)→X→Y",
+            format!("{}", arrow(arrow("X", "Y"), arrow("X", "Y")))
+        );
+    }
+}
@@ -2,31 +2,30 @@
 
 use std::collections::HashSet;
 
-use crate::notation::named::Term;
+use crate::notation::{generic, DeBruijn, Named};
 
 /// A trait for computing free variables on an expression.
 pub trait Variables {
+    /// Type used to represent variable names
+    type Name;
+
     /// Returns the free variables in a term.
-    fn free_variables(&self) -> HashSet<String>;
+    fn free_variables(&self) -> HashSet<Self::Name>;
 }
 
-impl Variables for Term {
+impl Variables for Named {
+    type Name = String;
+
     fn free_variables(&self) -> HashSet<String> {
-        match self {
-            Term::Variable(s) => {
-                vec![s.clone()].into_iter().collect()
-            },
-            Term::Abstraction(s, t) => {
-                let mut fv = t.free_variables();
-                fv.remove(s);
-                fv
-            },
-            Term::Application(t1, t2) => {
-                let mut fv = t1.free_variables();
-                fv.extend(t2.free_variables().into_iter());
-                fv
-            },
-        }
+        generic::free_variables(self)
+    }
+}
+
+impl Variables for DeBruijn {
+    type Name = u32;
+
+    fn free_variables(&self) -> HashSet<u32> {
+        generic::free_variables(self)
     }
 }
 
@@ -0,0 +1,266 @@
+//! Operations on deeply nested terms, which overflow the stack if anything recurses once per
+//! level, including dropping them.
+//!
+//! Each check runs at `DEPTH`, which is already deep enough to overflow a recursive
+//! implementation. Running them a million levels deep takes minutes in a debug build, so those
+//! tests are ignored. Run them with `cargo test --release -- --ignored`.
+use lambda_calculus::evaluation::{
+    Applicative, BetaEta, CallByNeed, CallByValue, EmptyContext, Evaluable, Full, HeadNormal,
+    HeadSpine, Heap, HybridApplicative, HybridNormal, Lazy, LocallyNamelessNormal, Normal,
+};
+use lambda_calculus::notation::{LocallyNameless, Notation};
+use lambda_calculus::notation::pretty::Pretty;
+use lambda_calculus::notation::render::Renderer;
+use lambda_calculus::path::{replace_at, Dir};
+use lambda_calculus::redex::{complete_development, contract};
+use lambda_calculus::{parse_de_bruijn, parse_one, remove_names, restore_names, DeBruijn, Named, Substitutable, Variables};
+
+/// The depth the checks run at by default
+const DEPTH: usize = 100_000;
+
+/// The depth the ignored tests run at
+const MILLION: usize = 1_000_000;
+
+/// Runs each check at `DEPTH`, and at `MILLION` when ignored tests are run
+macro_rules! deep_tests {
+    ($($check:ident),*) => {
+        mod at_default_depth {
+            $(
+                #[test]
+                fn $check() {
+                    super::$check(super::DEPTH)
+                }
+            )*
+        }
+
+        mod at_a_million_levels {
+            $(
+                #[test]
+                #[ignore]
+                fn $check() {
+                    super::$check(super::MILLION)
+                }
+            )*
+        }
+    };
+}
+
+/// Returns the Church numeral for `n`, `\s z.s (s (... z))`, as the ASCII printer prints it
+fn church_numeral(n: usize) -> String {
+    format!(r"\s z.{}s z{}", "s (".repeat(n - 1), ")".repeat(n - 1))
+}
+
+fn parses_and_prints_deep_term(depth: usize) {
+    let text = church_numeral(depth);
+    let term = parse_one(&text).unwrap();
+    assert_eq!(2 * depth + 3, term.size());
+    assert_eq!(depth + 2, term.depth());
+    assert_eq!(text, Pretty::new().ascii().print(&term));
+}
+
+fn parses_deeply_nested_abstractions(depth: usize) {
+    let text = format!(r"{}x", r"\x.".repeat(depth));
+    let term = parse_one(&text).unwrap();
+    assert_eq!(depth, term.depth());
+}
+
+fn clones_and_compares_deep_term(depth: usize) {
+    let term = parse_one(&church_numeral(depth)).unwrap();
+    let copy = term.clone();
+    assert!(term == copy);
+    assert!(term.is_alpha_equivalent(&copy));
+}
+
+fn converts_deep_term_to_de_bruijn_and_back(depth: usize) {
+    let term = parse_one(&church_numeral(depth)).unwrap();
+    let de_bruijn = remove_names(&term, &[]).unwrap();
+    let printed = Pretty::new().ascii().print_de_bruijn(&de_bruijn);
+    assert_eq!(format!(r"\.\.{}1 0{}", "1 (".repeat(depth - 1), ")".repeat(depth - 1)), printed);
+
+    let parsed = parse_de_bruijn(&printed).unwrap();
+    assert!(parsed == de_bruijn);
+    let restored = restore_names(&parsed, &[]).unwrap();
+    assert!(term.is_alpha_equivalent(&restored));
+}
+
+fn substitutes_into_deep_term(depth: usize) {
+    let body = parse_one(&format!("{}z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    assert!(body.free_variables().contains("z"));
+
+    let substituted = body.substitute("z", &Named::Variable("y".to_string()));
+    let expected = parse_one(&format!("{}y{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    assert!(substituted == expected);
+
+    let de_bruijn = DeBruijn::from(&body);
+    let shifted = de_bruijn.shift(1);
+    assert_eq!(vec![1, 2], {
+        let mut free: Vec<u32> = shifted.free_variables().into_iter().collect();
+        free.sort();
+        free
+    });
+}
+
+fn steps_deep_term_with_normal_order(depth: usize) {
+    let numeral = parse_one(&church_numeral(depth)).unwrap();
+
+    // A redex at the root, whose argument is deep
+    let term = parse_one(&format!(r"(\n.n) ({})", church_numeral(depth))).unwrap();
+    let stepped = Normal::new().step(&mut EmptyContext{}, term).unwrap();
+    assert!(stepped == numeral);
+
+    // A redex at the bottom of a deep term
+    let term = parse_one(&format!(r"{}(\x.x) z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    let stepped = Normal::new().step(&mut EmptyContext{}, term).unwrap();
+    let expected = parse_one(&format!("{}z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    assert!(stepped == expected);
+    assert!(Normal::new().step(&mut EmptyContext{}, stepped).is_none());
+}
+
+fn evaluates_deep_term_with_normal_order(depth: usize) {
+    let term = parse_one(&format!(r"{}(\x.x) ((\y.y) z){}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    let (evaluated, steps) = Normal::new().evaluate_counting_steps(&mut EmptyContext{}, term);
+    let expected = parse_one(&format!("{}z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    assert_eq!(2, steps);
+    assert!(evaluated == expected);
+}
+
+fn classifies_deep_terms(depth: usize) {
+    let numeral = parse_one(&church_numeral(depth)).unwrap();
+    assert!(numeral.is_normal_form());
+    assert!(numeral.is_head_normal_form());
+    assert!(!numeral.is_redex());
+
+    let term = parse_one(&spine(r"(\x.x) z", depth)).unwrap();
+    assert!(!term.is_normal_form());
+    assert!(!term.is_head_normal_form());
+    assert!(term.is_redex());
+
+    let de_bruijn = DeBruijn::from(&parse_one(&format!("{}z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap());
+    assert!(de_bruijn.is_normal_form());
+    assert!(de_bruijn.is_head_normal_form());
+    assert!(de_bruijn.has_free_variable(1));
+    assert!(!de_bruijn.has_free_variable(2));
+}
+
+fn renders_deep_term_as_dot(depth: usize) {
+    let dot = Renderer::with_back_edges().to_dot(&parse_one(&church_numeral(depth)).unwrap());
+    // A line per node, per edge and per back edge, besides the first and last
+    assert_eq!(2 + (2 * depth + 3) + (2 * depth + 2) + (depth + 1), dot.lines().count());
+}
+
+fn contracts_and_develops_deep_term(depth: usize) {
+    let term = parse_one(&format!(r"{}(\x.x) z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    let expected = parse_one(&format!("{}z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    assert!(contract(&term, 0).unwrap() == expected);
+    assert!(complete_development(&term) == expected);
+
+    let path = vec![Dir::Arg; depth];
+    assert!(replace_at(&term, &path, Named::Variable("z".to_string())).unwrap() == expected);
+}
+
+/// Returns `f a a ... a`, a spine of applications `count` levels deep
+fn spine(f: &str, count: usize) -> String {
+    format!("{}{}", f, " a".repeat(count))
+}
+
+fn evaluates_deep_spine_with_call_by_value(depth: usize) {
+    let term = parse_one(&spine(r"(\x.x) (\y.y)", depth)).unwrap();
+    let (evaluated, steps) = CallByValue::new().evaluate_counting_steps(&mut EmptyContext{}, term);
+    assert_eq!(1, steps);
+    assert!(evaluated == parse_one(&spine(r"(\y.y)", depth)).unwrap());
+}
+
+fn evaluates_deep_spine_with_lazy(depth: usize) {
+    let term = parse_one(&spine(r"(\x.x) (\y.y)", depth)).unwrap();
+    let (evaluated, steps) = Lazy::new().evaluate_counting_steps(&mut EmptyContext{}, term);
+    assert_eq!(2, steps);
+    assert!(evaluated == parse_one(&spine("a", depth - 1)).unwrap());
+}
+
+fn evaluates_deep_term_with_full(depth: usize) {
+    let term = parse_one(&format!(r"{}(\x.x) ((\y.y) z){}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    let (evaluated, steps) = Full::new().evaluate_counting_steps(&mut EmptyContext{}, term);
+    let expected = parse_one(&format!("{}z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    assert_eq!(2, steps);
+    assert!(evaluated == expected);
+}
+
+/// Evaluates `(\x.x) (\y.y) a ... a` to `a ... a`, contracting the two redexes at the bottom of
+/// the spine
+fn assert_evaluates_deep_spine<E: Evaluable<Term = Named>>(depth: usize, strategy: E, mut ctx: E::Context) {
+    let term = parse_one(&spine(r"(\x.x) (\y.y)", depth)).unwrap();
+    let (evaluated, steps) = strategy.evaluate_counting_steps(&mut ctx, term);
+    assert_eq!(2, steps);
+    assert!(evaluated == parse_one(&spine("a", depth - 1)).unwrap());
+}
+
+/// Evaluates `s (s (... ((\x.x) ((\y.y) z))))` to `s (s (... z))`, contracting the two redexes
+/// at the bottom of the arguments
+fn assert_evaluates_deep_argument<E: Evaluable<Term = Named>>(depth: usize, strategy: E, mut ctx: E::Context) {
+    let term = parse_one(&format!(r"{}(\x.x) ((\y.y) z){}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    let (evaluated, steps) = strategy.evaluate_counting_steps(&mut ctx, term);
+    let expected = parse_one(&format!("{}z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    assert_eq!(2, steps);
+    assert!(evaluated == expected);
+}
+
+fn evaluates_deep_spine_with_head_strategies(depth: usize) {
+    assert_evaluates_deep_spine(depth, HeadNormal::new(), EmptyContext{});
+    assert_evaluates_deep_spine(depth, HeadSpine::new(), EmptyContext{});
+}
+
+fn evaluates_deep_terms_with_applicative_order(depth: usize) {
+    assert_evaluates_deep_spine(depth, Applicative::new(), EmptyContext{});
+    assert_evaluates_deep_argument(depth, Applicative::new(), EmptyContext{});
+}
+
+fn evaluates_deep_terms_with_hybrid_strategies(depth: usize) {
+    assert_evaluates_deep_spine(depth, HybridNormal::new(), EmptyContext{});
+    assert_evaluates_deep_argument(depth, HybridNormal::new(), EmptyContext{});
+    assert_evaluates_deep_spine(depth, HybridApplicative::new(), EmptyContext{});
+    assert_evaluates_deep_argument(depth, HybridApplicative::new(), EmptyContext{});
+}
+
+fn evaluates_deep_terms_with_beta_eta(depth: usize) {
+    assert_evaluates_deep_spine(depth, BetaEta::new(), EmptyContext{});
+    assert_evaluates_deep_argument(depth, BetaEta::new(), EmptyContext{});
+}
+
+fn evaluates_deep_spine_with_call_by_need(depth: usize) {
+    assert_evaluates_deep_spine(depth, CallByNeed::new(), Heap::new());
+}
+
+fn evaluates_deep_terms_with_locally_nameless_normal_order(depth: usize) {
+    let term = LocallyNameless::from(&parse_one(&spine(r"(\x.x) (\y.y)", depth)).unwrap());
+    let (evaluated, steps) = LocallyNamelessNormal::new().evaluate_counting_steps(&mut EmptyContext{}, term);
+    assert_eq!(2, steps);
+    assert!(Named::from(&evaluated) == parse_one(&spine("a", depth - 1)).unwrap());
+
+    let term = parse_one(&format!(r"\s.{}(\x.x) ((\y.y) z){}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    let (evaluated, steps) = LocallyNamelessNormal::new().evaluate_counting_steps(&mut EmptyContext{}, LocallyNameless::from(&term));
+    let expected = parse_one(&format!(r"\s.{}z{}", "s (".repeat(depth), ")".repeat(depth))).unwrap();
+    assert_eq!(2, steps);
+    assert!(evaluated == LocallyNameless::from(&expected));
+}
+
+deep_tests!(
+    parses_and_prints_deep_term,
+    parses_deeply_nested_abstractions,
+    clones_and_compares_deep_term,
+    converts_deep_term_to_de_bruijn_and_back,
+    substitutes_into_deep_term,
+    steps_deep_term_with_normal_order,
+    evaluates_deep_term_with_normal_order,
+    classifies_deep_terms,
+    renders_deep_term_as_dot,
+    contracts_and_develops_deep_term,
+    evaluates_deep_spine_with_call_by_value,
+    evaluates_deep_spine_with_lazy,
+    evaluates_deep_term_with_full,
+    evaluates_deep_spine_with_head_strategies,
+    evaluates_deep_terms_with_applicative_order,
+    evaluates_deep_terms_with_hybrid_strategies,
+    evaluates_deep_terms_with_beta_eta,
+    evaluates_deep_spine_with_call_by_need,
+    evaluates_deep_terms_with_locally_nameless_normal_order
+);
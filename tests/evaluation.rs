@@ -0,0 +1,56 @@
+use lambda_calculus::evaluation::{CallByNeed, CallByValue, Cek, EmptyContext, Evaluable, Heap, Krivine, Lazy, Nbe, Normal};
+use lambda_calculus::parser::parse_one;
+use lambda_calculus::Named;
+
+const PLUS_ONE_ONE: &str = r"
+    c_1 = \s.\z.s z;
+    plus = \m.\n.\s.\z.m s (n s z);
+    plus c_1 c_1
+";
+
+fn parse(text: &str) -> Named {
+    parse_one(text).unwrap()
+}
+
+#[test]
+fn test_normal_order_finds_normal_form() {
+    assert_eq!(
+        parse(r"\s.\z.s (s z)"),
+        Normal::new().evaluate(&mut EmptyContext{}, parse(PLUS_ONE_ONE))
+    );
+}
+
+#[test]
+fn test_normal_order_discards_divergent_argument() {
+    assert_eq!(
+        parse("y"),
+        Normal::new().evaluate(&mut EmptyContext{}, parse(r"(\x.y) ((\x.x x) (\x.x x))"))
+    );
+}
+
+#[test]
+fn test_nbe_agrees_with_normal_order() {
+    let term = parse(PLUS_ONE_ONE);
+    assert!(
+        Nbe::new().normalize(&term).is_alpha_equivalent(&Normal::new().evaluate(&mut EmptyContext{}, term))
+    );
+}
+
+#[test]
+fn test_weak_strategies_stop_at_abstractions() {
+    let term = parse(r"(\x.\y.x) ((\a.a) \b.b)");
+    let expected = parse(r"\y.\b.b");
+    assert!(expected.is_alpha_equivalent(&CallByValue::new().evaluate(&mut EmptyContext{}, term.clone())));
+    assert!(expected.is_alpha_equivalent(&Cek::new().evaluate(&term)));
+    assert!(parse(r"\y.(\a.a) \b.b").is_alpha_equivalent(&Lazy::new().evaluate(&mut EmptyContext{}, term.clone())));
+    assert!(parse(r"\y.(\a.a) \b.b").is_alpha_equivalent(&Krivine::new().evaluate(&term)));
+}
+
+#[test]
+fn test_call_by_need_takes_fewer_steps_than_lazy() {
+    let term = parse(r"(\f.f (f z)) ((\id.id) (\x.x))");
+    let (lazy_result, lazy_steps) = Lazy::new().evaluate_counting_steps(&mut EmptyContext{}, term.clone());
+    let (need_result, need_steps) = CallByNeed::new().evaluate_counting_steps(&mut Heap::new(), term);
+    assert_eq!(lazy_result, need_result);
+    assert!(need_steps < lazy_steps);
+}
@@ -0,0 +1,57 @@
+use lambda_calculus::notation::pretty::Pretty;
+use lambda_calculus::{lambda, parse, parse_de_bruijn, parse_one, remove_names, restore_names, ConversionError, DeBruijn, Named, ParseError};
+
+#[test]
+fn test_parses_terms_with_macros() {
+    let terms = parse(r"id = \x.x; id y; id id").unwrap();
+    assert_eq!(vec![lambda!((|x| x) y), lambda!((|x| x) (|x| x))], terms);
+}
+
+#[test]
+fn test_reports_parse_errors() {
+    match parse_one(r"\x.").unwrap_err().downcast::<ParseError>() {
+        Ok(ParseError::MissingBody(1, _)) => {},
+        other => panic!("expected a missing body, got {:?}", other),
+    }
+    match parse_one("x)").unwrap_err().downcast::<ParseError>() {
+        Ok(ParseError::UnmatchedParenthesis(1, 2)) => {},
+        other => panic!("expected an unmatched parenthesis, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_printed_term_parses_back() {
+    let term = parse_one(r"\f.\x.f (f x) (\y.y)").unwrap();
+    assert_eq!(term, parse_one(&Pretty::new().ascii().print(&term)).unwrap());
+}
+
+#[test]
+fn test_parses_de_bruijn_term() {
+    let term = parse_de_bruijn(r"\. \. 1 0").unwrap();
+    assert_eq!(
+        DeBruijn::Abstraction(Box::new(DeBruijn::Abstraction(Box::new(DeBruijn::Application(
+            Box::new(DeBruijn::Variable(1)),
+            Box::new(DeBruijn::Variable(0)),
+        ))))),
+        term
+    );
+}
+
+#[test]
+fn test_macro_builds_named_terms() {
+    let id: Named = lambda!(|x| x);
+    assert_eq!(parse_one(r"\a.\b.(\x.x) a b").unwrap(), lambda!(|a, b| {id} a b));
+}
+
+#[test]
+fn test_converts_with_global_naming_context() {
+    let globals = vec!["id".to_string(), "const".to_string()];
+    let term = parse_one(r"\x.id (const x)").unwrap();
+    let de_bruijn = remove_names(&term, &globals).unwrap();
+    assert_eq!(parse_de_bruijn(r"\.2 (1 0)").unwrap(), de_bruijn);
+    assert!(term.is_alpha_equivalent(&restore_names(&de_bruijn, &globals).unwrap()));
+    assert_eq!(
+        Err(ConversionError::UnboundVariable("flip".to_string())),
+        remove_names(&parse_one("flip id").unwrap(), &globals)
+    );
+}
@@ -0,0 +1,17 @@
+use lambda_calculus::types::parser::{parse_one, parse_type};
+use lambda_calculus::types::{type_of, TypeError};
+
+#[test]
+fn test_type_checks_polymorphic_identity() {
+    let term = parse_one(r"\X.\x:X.x").unwrap();
+    assert!(type_of(&term).unwrap().is_equivalent(&parse_type(r"forall X.X -> X").unwrap()));
+}
+
+#[test]
+fn test_rejects_ill_typed_application() {
+    let term = parse_one(r"\X.\x:X.x x").unwrap();
+    match type_of(&term) {
+        Err(TypeError::NotAFunction(_, _)) => {},
+        result => panic!("expected NotAFunction, found {:?}", result.map(|ty| ty.to_string())),
+    }
+}